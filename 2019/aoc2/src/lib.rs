@@ -1,37 +1,16 @@
-const ADD: i32 = 1;
-const MUL: i32 = 2;
-const HLT: i32 = 99;
+use intcode::{Level, Machine};
 
-pub fn execute(mut v: Vec<i32>) -> Vec<i32> {
-    let mut pc: usize = 0;
-    loop {
-        // println!("PC: {:?}", pc);
-        match v[pc] {
-            ADD => {
-                let op1 = v[pc + 1] as usize;
-                let op2 = v[pc + 2] as usize;
-                let dst = v[pc + 3] as usize;
-                v[dst] = v[op1] + v[op2];
-                // println!("ADD: *{:?} + *{:?} => &{:?} ({:?})", op1, op2, dst, v[dst]);
-            }
-            MUL => {
-                let op1 = v[pc + 1] as usize;
-                let op2 = v[pc + 2] as usize;
-                let dst = v[pc + 3] as usize;
-                v[dst] = v[op1] * v[op2];
-                // println!("MUL: *{:?} * *{:?} => &{:?} ({:?})", op1, op2, dst, v[dst]);
-            }
-            HLT => break,
-            v => panic!("Unknown opcode {:?}", v),
-        }
-        pc += 4;
-    }
-    v
+pub fn execute(v: Vec<i64>) -> Vec<i64> {
+    let mut machine = Machine::new(v);
+    machine.set_level(Level::Day2);
+    machine.run().unwrap();
+    machine.into_memory().as_slice().to_vec()
 }
 
 #[cfg(test)]
 mod tests {
     use crate::execute;
+    use intcode::Program;
 
     #[test]
     fn ex1() {
@@ -63,13 +42,7 @@ mod tests {
 
     #[test]
     fn part1() {
-        let mut input = vec![
-            1, 0, 0, 3, 1, 1, 2, 3, 1, 3, 4, 3, 1, 5, 0, 3, 2, 10, 1, 19, 1, 19, 6, 23, 2, 13, 23,
-            27, 1, 27, 13, 31, 1, 9, 31, 35, 1, 35, 9, 39, 1, 39, 5, 43, 2, 6, 43, 47, 1, 47, 6,
-            51, 2, 51, 9, 55, 2, 55, 13, 59, 1, 59, 6, 63, 1, 10, 63, 67, 2, 67, 9, 71, 2, 6, 71,
-            75, 1, 75, 5, 79, 2, 79, 10, 83, 1, 5, 83, 87, 2, 9, 87, 91, 1, 5, 91, 95, 2, 13, 95,
-            99, 1, 99, 10, 103, 1, 103, 2, 107, 1, 107, 6, 0, 99, 2, 14, 0, 0,
-        ];
+        let mut input: Vec<i64> = Program::from_path("input.txt").unwrap().into();
 
         input[1] = 12;
         input[2] = 2;
@@ -80,21 +53,15 @@ mod tests {
 
     #[test]
     fn part2() {
-        let original = vec![
-            1, 0, 0, 3, 1, 1, 2, 3, 1, 3, 4, 3, 1, 5, 0, 3, 2, 10, 1, 19, 1, 19, 6, 23, 2, 13, 23,
-            27, 1, 27, 13, 31, 1, 9, 31, 35, 1, 35, 9, 39, 1, 39, 5, 43, 2, 6, 43, 47, 1, 47, 6,
-            51, 2, 51, 9, 55, 2, 55, 13, 59, 1, 59, 6, 63, 1, 10, 63, 67, 2, 67, 9, 71, 2, 6, 71,
-            75, 1, 75, 5, 79, 2, 79, 10, 83, 1, 5, 83, 87, 2, 9, 87, 91, 1, 5, 91, 95, 2, 13, 95,
-            99, 1, 99, 10, 103, 1, 103, 2, 107, 1, 107, 6, 0, 99, 2, 14, 0, 0,
-        ];
+        let original: Vec<i64> = Program::from_path("input.txt").unwrap().into();
         let mut answer = 0;
 
         'outer: for noun in 0..original.len() {
             print!(".");
             for verb in 0..original.len() {
                 let mut input = original.clone();
-                input[1] = noun as i32;
-                input[2] = verb as i32;
+                input[1] = noun as i64;
+                input[2] = verb as i64;
                 let output = execute(input);
                 if output[0] == 19690720 {
                     answer = 100 * noun + verb;
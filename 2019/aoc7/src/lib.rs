@@ -1,36 +1,12 @@
-const ADD: i64 = 1;
-const MUL: i64 = 2;
-const INP: i64 = 3;
-const OUT: i64 = 4;
-
-const JNZ: i64 = 5;
-const JZ: i64 = 6;
-const LES: i64 = 7;
-const EQU: i64 = 8;
-const ADJ: i64 = 9;
-
-const HLT: i64 = 99;
-
-const POSITION: i64 = 0;
-const IMMEDIATE: i64 = 1;
-const RELATIVE: i64 = 2;
-
-pub fn address(v: &Vec<i64>, base: usize, offset: usize, mode: i64) -> usize {
-    match mode {
-        POSITION => v[offset] as usize,
-        IMMEDIATE => offset,
-        RELATIVE => {
-            let op = v[offset];
-            let index = (base as i64 + op) as usize;
-            index
-        }
-        v => panic!("Unknown mode {:?}", v),
-    }
-}
+use std::collections::VecDeque;
+
+use intcode::{ErrorKind, IntcodeError, Level, Machine, Pipeline, Status};
 
-pub fn operand(v: &Vec<i64>, base: usize, offset: usize, mode: i64) -> i64 {
-    let address = address(v, base, offset, mode);
-    v[address]
+// The amplifiers run the day 5 instruction set.
+fn amplifier(v: Vec<i64>, input: &[i64]) -> Machine {
+    let mut machine = Machine::with_input(v, input);
+    machine.set_level(Level::Day5);
+    machine
 }
 
 pub fn execute(v: Vec<i64>) -> (Vec<i64>, Vec<i64>) {
@@ -41,109 +17,51 @@ pub fn execute_with_input(v: Vec<i64>, input: i64) -> (Vec<i64>, Vec<i64>) {
     execute_with_vec_input(v, vec![input])
 }
 
-pub fn execute_with_vec_input(mut v: Vec<i64>, mut input: Vec<i64>) -> (Vec<i64>, Vec<i64>) {
-    let mut pc: usize = 0;
+// The input is consumed from the end.
+pub fn execute_with_vec_input(v: Vec<i64>, mut input: Vec<i64>) -> (Vec<i64>, Vec<i64>) {
+    input.reverse();
+    let mut machine = Machine::new(v);
     let mut output = Vec::new();
-    let mut relative_base: usize = 0;
-    loop {
-        // println!("PC: {:?}", pc);
-        let opcode = v[pc];
-        let instruction = opcode % 100;
-        let m1 = (opcode / 100) % 10;
-        let m2 = (opcode / 1000) % 10;
-        let m3 = (opcode / 10000) % 10;
-        // println!(
-        //     "\n  {:04}: ins {:?} ({:?}) [{:?}, {:?}, {:?}]",
-        //     pc, instruction, opcode, m1, m2, m3
-        // );
-
-        match instruction {
-            ADD => {
-                let op1 = operand(&v, relative_base, pc + 1, m1);
-                let op2 = operand(&v, relative_base, pc + 2, m2);
-                let dst = address(&v, relative_base, pc + 3, m3);
-                v[dst] = op1 + op2;
-                // println!("{:04}: ADD: {:?} + {:?} => &{:?} ({:?})", pc, op1, op2, dst, v[dst]);
-                pc += 4;
-            }
-            MUL => {
-                let op1 = operand(&v, relative_base, pc + 1, m1);
-                let op2 = operand(&v, relative_base, pc + 2, m2);
-                let dst = address(&v, relative_base, pc + 3, m3);
-                v[dst] = op1 * op2;
-                // println!("{:04}: MUL: {:?} * {:?} => &{:?} ({:?})", pc, op1, op2, dst, v[dst]);
-                pc += 4;
-            }
-            INP => {
-                let dst = address(&v, relative_base, pc + 1, m1);
-                let inp = input.pop().expect("input missing");
-                println!("{:04}: INP: {:?} => {:?}", pc, inp, dst);
-                v[dst] = inp;
-                pc += 2;
-            }
-            OUT => {
-                let op1 = operand(&v, relative_base, pc + 1, m1);
-                output.push(op1);
-                println!("{:04}: OUT: {:?}", pc, output.last().unwrap());
-                pc += 2;
-            }
-            JNZ => {
-                let op1 = operand(&v, relative_base, pc + 1, m1);
-                let dst = operand(&v, relative_base, pc + 2, m2);
-                if op1 != 0 {
-                    pc = dst as usize;
-                } else {
-                    pc += 3;
-                }
-            }
-            JZ => {
-                let op1 = operand(&v, relative_base, pc + 1, m1);
-                let dst = operand(&v, relative_base, pc + 2, m2);
-                if op1 == 0 {
-                    pc = dst as usize;
-                } else {
-                    pc += 3;
-                }
-            }
-            LES => {
-                let op1 = operand(&v, relative_base, pc + 1, m1);
-                let op2 = operand(&v, relative_base, pc + 2, m2);
-                let dst = address(&v, relative_base, pc + 3, m3);
-                v[dst] = if op1 < op2 { 1 } else { 0 };
-                pc += 4;
-            }
-            EQU => {
-                let op1 = operand(&v, relative_base, pc + 1, m1);
-                let op2 = operand(&v, relative_base, pc + 2, m2);
-                let dst = address(&v, relative_base, pc + 3, m3);
-                v[dst] = if op1 == op2 { 1 } else { 0 };
-                pc += 4;
-            }
-            ADJ => {
-                let adjustment = operand(&v, relative_base, pc + 1, m1);
-                relative_base = (relative_base as i64 + adjustment) as usize;
-                // println!("{:04}: ADJ: {:?} => {:?}", pc, adjustment, relative_base);
-                pc += 2;
-            }
-            HLT => break,
-            v => panic!("Unknown opcode {:?}", v),
-        }
+    machine.run_io(VecDeque::from(input), &mut output).unwrap();
+    (output, machine.into_memory().as_slice().to_vec())
+}
+
+pub fn execute_phase(v: Vec<i64>, phases: Vec<i64>) -> Result<i64, IntcodeError> {
+    let amplifiers = phases.iter().enumerate().map(|(index, &phase)| {
+        let input = if index == 0 {
+            vec![phase, 0]
+        } else {
+            vec![phase]
+        };
+        amplifier(v.clone(), &input)
+    });
+    let finished = Pipeline::chain(amplifiers).run();
+    for outcome in finished.outcomes {
+        outcome.result?;
     }
-    (output, v)
+    Ok(finished.outputs.last().copied().unwrap_or(0))
 }
 
-pub fn execute_phase(mut v: Vec<i64>, mut phases: Vec<i64>) -> i64 {
+pub fn execute_feedback_loop(v: Vec<i64>, phases: Vec<i64>) -> Result<i64, IntcodeError> {
+    let mut amplifiers: Vec<Machine> = phases
+        .iter()
+        .map(|&phase| amplifier(v.clone(), &[phase]))
+        .collect();
     let mut output_signal = 0;
-    phases.reverse(); // because pop removes from the end
-    while phases.len() > 0 {
-        let phase = phases.pop().expect("no phase");
-        let (output, v) = execute_with_vec_input(v.clone(), vec![output_signal, phase]);
-        output_signal = *output.last().unwrap();
+    'feedback: loop {
+        for amplifier in amplifiers.iter_mut() {
+            amplifier.push_input(output_signal);
+            match amplifier.resume()? {
+                Status::Output(signal) => output_signal = signal,
+                Status::Halted => break 'feedback,
+                Status::NeedsInput => return Err(amplifier.fault(ErrorKind::InputExhausted)),
+            }
+        }
     }
-    output_signal
+    Ok(output_signal)
 }
 
-pub fn permutations(mut v: Vec<i64>) -> Vec<Vec<i64>> {
+pub fn permutations(v: Vec<i64>) -> Vec<Vec<i64>> {
     let len = v.len();
     let mut r = Vec::with_capacity(120); // fac(5)
     if len < 2 {
@@ -168,19 +86,29 @@ pub fn permutations(mut v: Vec<i64>) -> Vec<Vec<i64>> {
     r
 }
 
-pub fn maximize_thruster_signal(mut v: Vec<i64>) -> i64 {
-    permutations((0..5).collect())
+pub fn maximize_thruster_signal(v: Vec<i64>) -> Result<i64, IntcodeError> {
+    let signals = permutations((0..5).collect())
         .iter()
         .map(|phases| execute_phase(v.clone(), (*phases).to_vec()))
-        .max()
-        .unwrap()
+        .collect::<Result<Vec<i64>, IntcodeError>>()?;
+    Ok(signals.into_iter().max().unwrap())
+}
+
+pub fn maximize_feedback_signal(v: Vec<i64>) -> Result<i64, IntcodeError> {
+    let signals = permutations((5..=9).collect())
+        .iter()
+        .map(|phases| execute_feedback_loop(v.clone(), (*phases).to_vec()))
+        .collect::<Result<Vec<i64>, IntcodeError>>()?;
+    Ok(signals.into_iter().max().unwrap())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        execute, execute_phase, execute_with_input, maximize_thruster_signal, permutations,
+        execute, execute_feedback_loop, execute_phase, execute_with_input,
+        execute_with_vec_input, maximize_feedback_signal, maximize_thruster_signal, permutations,
     };
+    use intcode::{ErrorKind, Program};
 
     #[test]
     fn ex1() {
@@ -217,6 +145,13 @@ mod tests {
         assert_eq!(*res.0.last().unwrap(), 33);
     }
 
+    #[test]
+    fn input_from_the_end() {
+        let input = vec![3, 0, 3, 1, 4, 0, 4, 1, 99];
+        let res = execute_with_vec_input(input, vec![1, 2]);
+        assert_eq!(res.0, vec![2, 1]);
+    }
+
     #[test]
     fn ex4() {
         let input = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
@@ -274,9 +209,7 @@ mod tests {
             109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
         ];
 
-        let mut i2 = input.clone();
-        i2.resize(2048, 0);
-        let output = execute(i2);
+        let output = execute(input.clone());
         assert_eq!(output.0, input);
     }
 
@@ -310,14 +243,20 @@ mod tests {
             3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
         ];
         let output_signal = execute_phase(input, vec![4, 3, 2, 1, 0]);
-        assert_eq!(output_signal, 43210);
+        assert_eq!(output_signal.unwrap(), 43210);
+    }
+    #[test]
+    fn faulting_phase() {
+        let input = vec![3, 15, 42, 99];
+        let error = execute_phase(input, vec![4, 3, 2, 1, 0]).unwrap_err();
+        assert_eq!((error.kind, error.pc), (ErrorKind::UnknownOpcode, 2));
     }
     #[test]
     fn max_phase1() {
         let input = vec![
             3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
         ];
-        let output_signal = maximize_thruster_signal(input);
+        let output_signal = maximize_thruster_signal(input).unwrap();
         assert_eq!(output_signal, 43210);
     }
     #[test]
@@ -326,7 +265,7 @@ mod tests {
             3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23,
             99, 0, 0,
         ];
-        let output_signal = maximize_thruster_signal(input);
+        let output_signal = maximize_thruster_signal(input).unwrap();
         assert_eq!(output_signal, 54321);
     }
     #[test]
@@ -335,10 +274,45 @@ mod tests {
             3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1,
             33, 31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
         ];
-        let output_signal = maximize_thruster_signal(input);
+        let output_signal = maximize_thruster_signal(input).unwrap();
         assert_eq!(output_signal, 65210);
     }
     #[test]
+    fn exec_feedback1() {
+        let input = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let output_signal = execute_feedback_loop(input, vec![9, 8, 7, 6, 5]);
+        assert_eq!(output_signal.unwrap(), 139629729);
+    }
+    #[test]
+    fn starved_feedback() {
+        // wants a second signal before it outputs anything
+        let input = vec![3, 9, 3, 9, 3, 9, 4, 9, 99, 0];
+        let error = execute_feedback_loop(input, vec![9, 8]).unwrap_err();
+        assert_eq!((error.kind, error.pc), (ErrorKind::InputExhausted, 4));
+    }
+    #[test]
+    fn max_feedback1() {
+        let input = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let output_signal = maximize_feedback_signal(input).unwrap();
+        assert_eq!(output_signal, 139629729);
+    }
+    #[test]
+    fn max_feedback2() {
+        let input = vec![
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ];
+        let output_signal = maximize_feedback_signal(input).unwrap();
+        assert_eq!(output_signal, 18216);
+    }
+    #[test]
     fn permute() {
         let p = permutations((0..5).collect());
         for v in &p {
@@ -349,184 +323,39 @@ mod tests {
 
     #[test]
     fn day5part1() {
-        let input = vec![
-            3, 225, 1, 225, 6, 6, 1100, 1, 238, 225, 104, 0, 1002, 148, 28, 224, 1001, 224, -672,
-            224, 4, 224, 1002, 223, 8, 223, 101, 3, 224, 224, 1, 224, 223, 223, 1102, 8, 21, 225,
-            1102, 13, 10, 225, 1102, 21, 10, 225, 1102, 6, 14, 225, 1102, 94, 17, 225, 1, 40, 173,
-            224, 1001, 224, -90, 224, 4, 224, 102, 8, 223, 223, 1001, 224, 4, 224, 1, 224, 223,
-            223, 2, 35, 44, 224, 101, -80, 224, 224, 4, 224, 102, 8, 223, 223, 101, 6, 224, 224, 1,
-            223, 224, 223, 1101, 26, 94, 224, 101, -120, 224, 224, 4, 224, 102, 8, 223, 223, 1001,
-            224, 7, 224, 1, 224, 223, 223, 1001, 52, 70, 224, 101, -87, 224, 224, 4, 224, 1002,
-            223, 8, 223, 1001, 224, 2, 224, 1, 223, 224, 223, 1101, 16, 92, 225, 1101, 59, 24, 225,
-            102, 83, 48, 224, 101, -1162, 224, 224, 4, 224, 102, 8, 223, 223, 101, 4, 224, 224, 1,
-            223, 224, 223, 1101, 80, 10, 225, 101, 5, 143, 224, 1001, 224, -21, 224, 4, 224, 1002,
-            223, 8, 223, 1001, 224, 6, 224, 1, 223, 224, 223, 1102, 94, 67, 224, 101, -6298, 224,
-            224, 4, 224, 102, 8, 223, 223, 1001, 224, 3, 224, 1, 224, 223, 223, 4, 223, 99, 0, 0,
-            0, 677, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1105, 0, 99999, 1105, 227, 247, 1105, 1,
-            99999, 1005, 227, 99999, 1005, 0, 256, 1105, 1, 99999, 1106, 227, 99999, 1106, 0, 265,
-            1105, 1, 99999, 1006, 0, 99999, 1006, 227, 274, 1105, 1, 99999, 1105, 1, 280, 1105, 1,
-            99999, 1, 225, 225, 225, 1101, 294, 0, 0, 105, 1, 0, 1105, 1, 99999, 1106, 0, 300,
-            1105, 1, 99999, 1, 225, 225, 225, 1101, 314, 0, 0, 106, 0, 0, 1105, 1, 99999, 108, 677,
-            677, 224, 102, 2, 223, 223, 1005, 224, 329, 101, 1, 223, 223, 1107, 677, 226, 224, 102,
-            2, 223, 223, 1006, 224, 344, 101, 1, 223, 223, 1107, 226, 226, 224, 102, 2, 223, 223,
-            1006, 224, 359, 101, 1, 223, 223, 1108, 677, 677, 224, 102, 2, 223, 223, 1005, 224,
-            374, 101, 1, 223, 223, 8, 677, 226, 224, 1002, 223, 2, 223, 1005, 224, 389, 101, 1,
-            223, 223, 108, 226, 677, 224, 1002, 223, 2, 223, 1006, 224, 404, 1001, 223, 1, 223,
-            107, 677, 677, 224, 102, 2, 223, 223, 1006, 224, 419, 101, 1, 223, 223, 1007, 226, 226,
-            224, 102, 2, 223, 223, 1005, 224, 434, 101, 1, 223, 223, 1007, 677, 677, 224, 102, 2,
-            223, 223, 1005, 224, 449, 1001, 223, 1, 223, 8, 677, 677, 224, 1002, 223, 2, 223, 1006,
-            224, 464, 101, 1, 223, 223, 1108, 677, 226, 224, 1002, 223, 2, 223, 1005, 224, 479,
-            101, 1, 223, 223, 7, 677, 226, 224, 1002, 223, 2, 223, 1005, 224, 494, 101, 1, 223,
-            223, 1008, 677, 677, 224, 1002, 223, 2, 223, 1006, 224, 509, 1001, 223, 1, 223, 1007,
-            226, 677, 224, 1002, 223, 2, 223, 1006, 224, 524, 1001, 223, 1, 223, 107, 226, 226,
-            224, 1002, 223, 2, 223, 1006, 224, 539, 1001, 223, 1, 223, 1107, 226, 677, 224, 102, 2,
-            223, 223, 1005, 224, 554, 101, 1, 223, 223, 1108, 226, 677, 224, 102, 2, 223, 223,
-            1006, 224, 569, 101, 1, 223, 223, 108, 226, 226, 224, 1002, 223, 2, 223, 1006, 224,
-            584, 1001, 223, 1, 223, 7, 226, 226, 224, 1002, 223, 2, 223, 1006, 224, 599, 101, 1,
-            223, 223, 8, 226, 677, 224, 102, 2, 223, 223, 1005, 224, 614, 101, 1, 223, 223, 7, 226,
-            677, 224, 1002, 223, 2, 223, 1005, 224, 629, 101, 1, 223, 223, 1008, 226, 677, 224,
-            1002, 223, 2, 223, 1006, 224, 644, 101, 1, 223, 223, 107, 226, 677, 224, 1002, 223, 2,
-            223, 1005, 224, 659, 1001, 223, 1, 223, 1008, 226, 226, 224, 1002, 223, 2, 223, 1006,
-            224, 674, 1001, 223, 1, 223, 4, 223, 99, 226,
-        ];
+        let input: Vec<i64> = Program::from_path("../aoc5/input.txt").unwrap().into();
 
-        let mut i2 = input.clone();
-        i2.resize(8096, 0);
-        let output = execute_with_input(i2, 1);
+        let output = execute_with_input(input, 1);
         assert_eq!(*output.0.last().unwrap(), 7566643);
     }
 
     #[test]
     fn day7part1() {
-        let input = vec![
-            3,8,1001,8,10,8,105,1,0,0,21,38,63,76,89,106,187,268,349,430,99999,3,9,1001,9,5,9,102,3,9,9,1001,9,2,9,4,9,99,3,9,101,4,9,9,102,3,9,9,101,4,9,9,1002,9,3,9,101,2,9,9,4,9,99,3,9,101,5,9,9,1002,9,4,9,4,9,99,3,9,101,2,9,9,1002,9,5,9,4,9,99,3,9,1001,9,5,9,1002,9,5,9,1001,9,5,9,4,9,99,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,1001,9,1,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,1001,9,1,9,4,9,3,9,101,1,9,9,4,9,3,9,102,2,9,9,4,9,3,9,1002,9,2,9,4,9,99,3,9,101,2,9,9,4,9,3,9,101,2,9,9,4,9,3,9,101,2,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,1001,9,1,9,4,9,3,9,1002,9,2,9,4,9,3,9,1001,9,1,9,4,9,3,9,101,2,9,9,4,9,99,3,9,1002,9,2,9,4,9,3,9,1001,9,2,9,4,9,3,9,101,2,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,1001,9,1,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,2,9,9,4,9,3,9,102,2,9,9,4,9,3,9,102,2,9,9,4,9,99,3,9,102,2,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,1001,9,2,9,4,9,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,101,1,9,9,4,9,3,9,101,1,9,9,4,9,3,9,101,2,9,9,4,9,99,3,9,1002,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,1001,9,1,9,4,9,3,9,101,1,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,99
-        ];
-        let output_signal = maximize_thruster_signal(input);
+        let input: Vec<i64> = Program::from_path("input.txt").unwrap().into();
+        let output_signal = maximize_thruster_signal(input).unwrap();
         assert_eq!(output_signal, 21860);
     }
 
+    #[test]
+    fn day7part2() {
+        let input: Vec<i64> = Program::from_path("input.txt").unwrap().into();
+        let output_signal = maximize_feedback_signal(input).unwrap();
+        assert_eq!(output_signal, 2645740);
+    }
+
     #[test]
     fn day9part1() {
-        let input = vec![
-            1102, 34463338, 34463338, 63, 1007, 63, 34463338, 63, 1005, 63, 53, 1102, 1, 3, 1000,
-            109, 988, 209, 12, 9, 1000, 209, 6, 209, 3, 203, 0, 1008, 1000, 1, 63, 1005, 63, 65,
-            1008, 1000, 2, 63, 1005, 63, 904, 1008, 1000, 0, 63, 1005, 63, 58, 4, 25, 104, 0, 99,
-            4, 0, 104, 0, 99, 4, 17, 104, 0, 99, 0, 0, 1101, 0, 493, 1024, 1102, 1, 38, 1015, 1101,
-            20, 0, 1011, 1101, 0, 509, 1026, 1101, 0, 32, 1018, 1101, 0, 333, 1022, 1102, 1, 0,
-            1020, 1101, 326, 0, 1023, 1101, 0, 33, 1010, 1101, 21, 0, 1016, 1101, 25, 0, 1004,
-            1102, 28, 1, 1008, 1102, 1, 506, 1027, 1102, 488, 1, 1025, 1101, 0, 27, 1013, 1101, 1,
-            0, 1021, 1101, 0, 34, 1019, 1101, 607, 0, 1028, 1102, 1, 23, 1003, 1102, 26, 1, 1007,
-            1102, 29, 1, 1009, 1101, 31, 0, 1000, 1102, 37, 1, 1012, 1101, 30, 0, 1005, 1101, 602,
-            0, 1029, 1101, 36, 0, 1002, 1102, 1, 22, 1001, 1102, 1, 35, 1014, 1102, 24, 1, 1006,
-            1102, 39, 1, 1017, 109, 4, 21102, 40, 1, 6, 1008, 1010, 40, 63, 1005, 63, 203, 4, 187,
-            1106, 0, 207, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 13, 1206, 3, 221, 4, 213, 1106, 0,
-            225, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -5, 1208, -9, 22, 63, 1005, 63, 241, 1106,
-            0, 247, 4, 231, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -5, 21107, 41, 40, 3, 1005,
-            1010, 263, 1106, 0, 269, 4, 253, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -1, 1202, 3, 1,
-            63, 1008, 63, 29, 63, 1005, 63, 295, 4, 275, 1001, 64, 1, 64, 1106, 0, 295, 1002, 64,
-            2, 64, 109, 16, 21108, 42, 42, -8, 1005, 1014, 313, 4, 301, 1105, 1, 317, 1001, 64, 1,
-            64, 1002, 64, 2, 64, 109, -4, 2105, 1, 5, 1001, 64, 1, 64, 1105, 1, 335, 4, 323, 1002,
-            64, 2, 64, 109, -5, 1207, -4, 28, 63, 1005, 63, 355, 1001, 64, 1, 64, 1105, 1, 357, 4,
-            341, 1002, 64, 2, 64, 109, 2, 21102, 43, 1, -1, 1008, 1014, 45, 63, 1005, 63, 377,
-            1106, 0, 383, 4, 363, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -10, 1208, -3, 36, 63,
-            1005, 63, 401, 4, 389, 1106, 0, 405, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 6, 21107,
-            44, 45, 1, 1005, 1012, 423, 4, 411, 1105, 1, 427, 1001, 64, 1, 64, 1002, 64, 2, 64,
-            109, 4, 21101, 45, 0, 3, 1008, 1018, 45, 63, 1005, 63, 453, 4, 433, 1001, 64, 1, 64,
-            1105, 1, 453, 1002, 64, 2, 64, 109, -23, 2101, 0, 10, 63, 1008, 63, 36, 63, 1005, 63,
-            475, 4, 459, 1106, 0, 479, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 26, 2105, 1, 6, 4,
-            485, 1105, 1, 497, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 4, 2106, 0, 5, 1105, 1, 515,
-            4, 503, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -25, 1201, 10, 0, 63, 1008, 63, 26, 63,
-            1005, 63, 537, 4, 521, 1105, 1, 541, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 18, 21101,
-            46, 0, -1, 1008, 1014, 43, 63, 1005, 63, 565, 1001, 64, 1, 64, 1106, 0, 567, 4, 547,
-            1002, 64, 2, 64, 109, -6, 1201, -4, 0, 63, 1008, 63, 33, 63, 1005, 63, 587, 1105, 1,
-            593, 4, 573, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 22, 2106, 0, -3, 4, 599, 1105, 1,
-            611, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -28, 2102, 1, -2, 63, 1008, 63, 22, 63,
-            1005, 63, 633, 4, 617, 1105, 1, 637, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -1, 21108,
-            47, 44, 9, 1005, 1011, 653, 1105, 1, 659, 4, 643, 1001, 64, 1, 64, 1002, 64, 2, 64,
-            109, 10, 2107, 24, -8, 63, 1005, 63, 681, 4, 665, 1001, 64, 1, 64, 1105, 1, 681, 1002,
-            64, 2, 64, 109, -11, 2107, 31, 4, 63, 1005, 63, 697, 1106, 0, 703, 4, 687, 1001, 64, 1,
-            64, 1002, 64, 2, 64, 109, 8, 2101, 0, -8, 63, 1008, 63, 23, 63, 1005, 63, 727, 1001,
-            64, 1, 64, 1105, 1, 729, 4, 709, 1002, 64, 2, 64, 109, -16, 2108, 21, 10, 63, 1005, 63,
-            749, 1001, 64, 1, 64, 1106, 0, 751, 4, 735, 1002, 64, 2, 64, 109, 17, 2108, 36, -8, 63,
-            1005, 63, 769, 4, 757, 1105, 1, 773, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -10, 1207,
-            1, 23, 63, 1005, 63, 791, 4, 779, 1105, 1, 795, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
-            -3, 2102, 1, 6, 63, 1008, 63, 22, 63, 1005, 63, 815, 1106, 0, 821, 4, 801, 1001, 64, 1,
-            64, 1002, 64, 2, 64, 109, 16, 1205, 7, 837, 1001, 64, 1, 64, 1105, 1, 839, 4, 827,
-            1002, 64, 2, 64, 109, -5, 1202, 0, 1, 63, 1008, 63, 30, 63, 1005, 63, 863, 1001, 64, 1,
-            64, 1106, 0, 865, 4, 845, 1002, 64, 2, 64, 109, 4, 1205, 9, 883, 4, 871, 1001, 64, 1,
-            64, 1106, 0, 883, 1002, 64, 2, 64, 109, 16, 1206, -7, 899, 1001, 64, 1, 64, 1106, 0,
-            901, 4, 889, 4, 64, 99, 21102, 1, 27, 1, 21101, 915, 0, 0, 1105, 1, 922, 21201, 1,
-            47633, 1, 204, 1, 99, 109, 3, 1207, -2, 3, 63, 1005, 63, 964, 21201, -2, -1, 1, 21102,
-            942, 1, 0, 1105, 1, 922, 22102, 1, 1, -1, 21201, -2, -3, 1, 21101, 957, 0, 0, 1106, 0,
-            922, 22201, 1, -1, -2, 1105, 1, 968, 22101, 0, -2, -2, 109, -3, 2106, 0, 0,
-        ];
+        let input: Vec<i64> = Program::from_path("../aoc9/input.txt").unwrap().into();
 
-        let mut i2 = input.clone();
-        i2.resize(8096, 0);
-        let output = execute_with_input(i2, 1);
+        let output = execute_with_input(input, 1);
         assert_eq!(*output.0.last().unwrap(), 3345854957);
     }
 
     #[test]
     fn day9part2() {
-        let input = vec![
-            1102, 34463338, 34463338, 63, 1007, 63, 34463338, 63, 1005, 63, 53, 1102, 1, 3, 1000,
-            109, 988, 209, 12, 9, 1000, 209, 6, 209, 3, 203, 0, 1008, 1000, 1, 63, 1005, 63, 65,
-            1008, 1000, 2, 63, 1005, 63, 904, 1008, 1000, 0, 63, 1005, 63, 58, 4, 25, 104, 0, 99,
-            4, 0, 104, 0, 99, 4, 17, 104, 0, 99, 0, 0, 1101, 0, 493, 1024, 1102, 1, 38, 1015, 1101,
-            20, 0, 1011, 1101, 0, 509, 1026, 1101, 0, 32, 1018, 1101, 0, 333, 1022, 1102, 1, 0,
-            1020, 1101, 326, 0, 1023, 1101, 0, 33, 1010, 1101, 21, 0, 1016, 1101, 25, 0, 1004,
-            1102, 28, 1, 1008, 1102, 1, 506, 1027, 1102, 488, 1, 1025, 1101, 0, 27, 1013, 1101, 1,
-            0, 1021, 1101, 0, 34, 1019, 1101, 607, 0, 1028, 1102, 1, 23, 1003, 1102, 26, 1, 1007,
-            1102, 29, 1, 1009, 1101, 31, 0, 1000, 1102, 37, 1, 1012, 1101, 30, 0, 1005, 1101, 602,
-            0, 1029, 1101, 36, 0, 1002, 1102, 1, 22, 1001, 1102, 1, 35, 1014, 1102, 24, 1, 1006,
-            1102, 39, 1, 1017, 109, 4, 21102, 40, 1, 6, 1008, 1010, 40, 63, 1005, 63, 203, 4, 187,
-            1106, 0, 207, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 13, 1206, 3, 221, 4, 213, 1106, 0,
-            225, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -5, 1208, -9, 22, 63, 1005, 63, 241, 1106,
-            0, 247, 4, 231, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -5, 21107, 41, 40, 3, 1005,
-            1010, 263, 1106, 0, 269, 4, 253, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -1, 1202, 3, 1,
-            63, 1008, 63, 29, 63, 1005, 63, 295, 4, 275, 1001, 64, 1, 64, 1106, 0, 295, 1002, 64,
-            2, 64, 109, 16, 21108, 42, 42, -8, 1005, 1014, 313, 4, 301, 1105, 1, 317, 1001, 64, 1,
-            64, 1002, 64, 2, 64, 109, -4, 2105, 1, 5, 1001, 64, 1, 64, 1105, 1, 335, 4, 323, 1002,
-            64, 2, 64, 109, -5, 1207, -4, 28, 63, 1005, 63, 355, 1001, 64, 1, 64, 1105, 1, 357, 4,
-            341, 1002, 64, 2, 64, 109, 2, 21102, 43, 1, -1, 1008, 1014, 45, 63, 1005, 63, 377,
-            1106, 0, 383, 4, 363, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -10, 1208, -3, 36, 63,
-            1005, 63, 401, 4, 389, 1106, 0, 405, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 6, 21107,
-            44, 45, 1, 1005, 1012, 423, 4, 411, 1105, 1, 427, 1001, 64, 1, 64, 1002, 64, 2, 64,
-            109, 4, 21101, 45, 0, 3, 1008, 1018, 45, 63, 1005, 63, 453, 4, 433, 1001, 64, 1, 64,
-            1105, 1, 453, 1002, 64, 2, 64, 109, -23, 2101, 0, 10, 63, 1008, 63, 36, 63, 1005, 63,
-            475, 4, 459, 1106, 0, 479, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 26, 2105, 1, 6, 4,
-            485, 1105, 1, 497, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 4, 2106, 0, 5, 1105, 1, 515,
-            4, 503, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -25, 1201, 10, 0, 63, 1008, 63, 26, 63,
-            1005, 63, 537, 4, 521, 1105, 1, 541, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 18, 21101,
-            46, 0, -1, 1008, 1014, 43, 63, 1005, 63, 565, 1001, 64, 1, 64, 1106, 0, 567, 4, 547,
-            1002, 64, 2, 64, 109, -6, 1201, -4, 0, 63, 1008, 63, 33, 63, 1005, 63, 587, 1105, 1,
-            593, 4, 573, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 22, 2106, 0, -3, 4, 599, 1105, 1,
-            611, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -28, 2102, 1, -2, 63, 1008, 63, 22, 63,
-            1005, 63, 633, 4, 617, 1105, 1, 637, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -1, 21108,
-            47, 44, 9, 1005, 1011, 653, 1105, 1, 659, 4, 643, 1001, 64, 1, 64, 1002, 64, 2, 64,
-            109, 10, 2107, 24, -8, 63, 1005, 63, 681, 4, 665, 1001, 64, 1, 64, 1105, 1, 681, 1002,
-            64, 2, 64, 109, -11, 2107, 31, 4, 63, 1005, 63, 697, 1106, 0, 703, 4, 687, 1001, 64, 1,
-            64, 1002, 64, 2, 64, 109, 8, 2101, 0, -8, 63, 1008, 63, 23, 63, 1005, 63, 727, 1001,
-            64, 1, 64, 1105, 1, 729, 4, 709, 1002, 64, 2, 64, 109, -16, 2108, 21, 10, 63, 1005, 63,
-            749, 1001, 64, 1, 64, 1106, 0, 751, 4, 735, 1002, 64, 2, 64, 109, 17, 2108, 36, -8, 63,
-            1005, 63, 769, 4, 757, 1105, 1, 773, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -10, 1207,
-            1, 23, 63, 1005, 63, 791, 4, 779, 1105, 1, 795, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
-            -3, 2102, 1, 6, 63, 1008, 63, 22, 63, 1005, 63, 815, 1106, 0, 821, 4, 801, 1001, 64, 1,
-            64, 1002, 64, 2, 64, 109, 16, 1205, 7, 837, 1001, 64, 1, 64, 1105, 1, 839, 4, 827,
-            1002, 64, 2, 64, 109, -5, 1202, 0, 1, 63, 1008, 63, 30, 63, 1005, 63, 863, 1001, 64, 1,
-            64, 1106, 0, 865, 4, 845, 1002, 64, 2, 64, 109, 4, 1205, 9, 883, 4, 871, 1001, 64, 1,
-            64, 1106, 0, 883, 1002, 64, 2, 64, 109, 16, 1206, -7, 899, 1001, 64, 1, 64, 1106, 0,
-            901, 4, 889, 4, 64, 99, 21102, 1, 27, 1, 21101, 915, 0, 0, 1105, 1, 922, 21201, 1,
-            47633, 1, 204, 1, 99, 109, 3, 1207, -2, 3, 63, 1005, 63, 964, 21201, -2, -1, 1, 21102,
-            942, 1, 0, 1105, 1, 922, 22102, 1, 1, -1, 21201, -2, -3, 1, 21101, 957, 0, 0, 1106, 0,
-            922, 22201, 1, -1, -2, 1105, 1, 968, 22101, 0, -2, -2, 109, -3, 2106, 0, 0,
-        ];
-
-        let mut i2 = input.clone();
-        i2.resize(65536, 0);
+        let input: Vec<i64> = Program::from_path("../aoc9/input.txt").unwrap().into();
 
-        let output = execute_with_input(i2, 2);
+        let output = execute_with_input(input, 2);
 
         assert_eq!(*output.0.last().unwrap(), 68938);
     }
@@ -0,0 +1,231 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::error::{ErrorKind, IntcodeError};
+use crate::machine::{Machine, Status};
+use crate::observer::Observer;
+
+/// A run of output: ASCII text, or a value too large to be a character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunk {
+    Text(String),
+    Value(i64),
+}
+
+fn is_ascii(value: i64) -> bool {
+    (0..128).contains(&value)
+}
+
+/// Splits output into text and raw values, joining consecutive characters.
+pub fn decode(values: &[i64]) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    for &value in values {
+        match chunks.last_mut() {
+            Some(Chunk::Text(text)) if is_ascii(value) => text.push(value as u8 as char),
+            _ if is_ascii(value) => chunks.push(Chunk::Text((value as u8 as char).to_string())),
+            _ => chunks.push(Chunk::Value(value)),
+        }
+    }
+    chunks
+}
+
+/// Output as text, with raw values written out as numbers.
+pub fn render(values: &[i64]) -> String {
+    values
+        .iter()
+        .map(|&value| {
+            if is_ascii(value) {
+                (value as u8 as char).to_string()
+            } else {
+                value.to_string()
+            }
+        })
+        .collect()
+}
+
+impl Machine {
+    /// Queues `line` as ASCII codes followed by a newline.
+    pub fn push_line(&mut self, line: &str) {
+        line.bytes()
+            .chain(Some(b'\n'))
+            .for_each(|byte| self.push_input(byte as i64));
+    }
+}
+
+#[derive(Debug)]
+pub enum AsciiError {
+    Io(io::Error),
+    Machine(IntcodeError),
+}
+
+impl fmt::Display for AsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsciiError::Io(error) => write!(f, "{}", error),
+            AsciiError::Machine(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for AsciiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AsciiError::Io(error) => Some(error),
+            AsciiError::Machine(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for AsciiError {
+    fn from(error: io::Error) -> AsciiError {
+        AsciiError::Io(error)
+    }
+}
+
+impl From<IntcodeError> for AsciiError {
+    fn from(error: IntcodeError) -> AsciiError {
+        AsciiError::Machine(error)
+    }
+}
+
+/// Runs the machine as a text program: output is written to `output` as it
+/// is produced, raw values on lines of their own, and whenever the machine
+/// asks for input a line is read from `input`. End of input while the
+/// machine still wants more is an `InputExhausted` fault.
+pub fn interact<R: BufRead, W: Write>(
+    machine: &mut Machine,
+    input: R,
+    output: W,
+) -> Result<(), AsciiError> {
+    interact_with(machine, input, output, &mut ())
+}
+
+pub fn interact_with<R: BufRead, W: Write, O: Observer>(
+    machine: &mut Machine,
+    mut input: R,
+    mut output: W,
+    observer: &mut O,
+) -> Result<(), AsciiError> {
+    let mut line_start = true;
+    loop {
+        match machine.resume_with(observer)? {
+            Status::Output(value) => {
+                machine.output.pop();
+                if is_ascii(value) {
+                    output.write_all(&[value as u8])?;
+                    line_start = value == b'\n' as i64;
+                } else {
+                    if !line_start {
+                        writeln!(output)?;
+                    }
+                    writeln!(output, "{}", value)?;
+                    line_start = true;
+                }
+            }
+            Status::NeedsInput => {
+                output.flush()?;
+                let mut line = String::new();
+                if input.read_line(&mut line)? == 0 {
+                    return Err(machine.fault(ErrorKind::InputExhausted).into());
+                }
+                machine.push_line(line.trim_end_matches(&['\r', '\n'][..]));
+            }
+            Status::Halted => {
+                output.flush()?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// `interact` on standard input and output.
+pub fn interact_stdio(machine: &mut Machine) -> Result<(), AsciiError> {
+    interact(machine, io::stdin().lock(), io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ascii::{decode, interact, render, AsciiError, Chunk};
+    use crate::asm::assemble;
+    use crate::{ErrorKind, Machine};
+
+    // Prints a prompt, then echoes each line in upper case until it reads
+    // an empty line, and finally outputs 1000.
+    fn shout() -> Machine {
+        let source = "
+                OUT #62
+                OUT #32
+            read:
+                INP -> [c]
+                EQU [c], #10 -> [t]
+                JNZ [t], #newline
+                LES [c], #97 -> [t]
+                JNZ [t], #print
+                ADD [c], #-32 -> [c]
+            print:
+                OUT [c]
+                ADD #0, #1 -> [seen]
+                JNZ #1, #read
+            newline:
+                JZ [seen], #done
+                OUT #10
+                ADD #0, #0 -> [seen]
+                JNZ #1, #read
+            done:
+                OUT #1000
+                HLT
+            c: db 0
+            t: db 0
+            seen: db 0
+        ";
+        Machine::new(assemble(source).unwrap())
+    }
+
+    #[test]
+    fn decodes_text_and_values() {
+        let values = [72, 105, 10, 1000, 33, 128, -1];
+        assert_eq!(
+            decode(&values),
+            vec![
+                Chunk::Text("Hi\n".to_string()),
+                Chunk::Value(1000),
+                Chunk::Text("!".to_string()),
+                Chunk::Value(128),
+                Chunk::Value(-1),
+            ]
+        );
+        assert_eq!(render(&values), "Hi\n1000!128-1");
+    }
+
+    #[test]
+    fn pushes_lines() {
+        let mut machine = shout();
+        machine.push_line("abc");
+        machine.push_line("");
+        machine.run().unwrap();
+        assert_eq!(render(machine.output()), "> ABC\n1000");
+    }
+
+    #[test]
+    fn interacts_line_by_line() {
+        let mut machine = shout();
+        let mut output = Vec::new();
+        interact(&mut machine, "hello\r\nworld\n\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "> HELLO\nWORLD\n1000\n");
+    }
+
+    #[test]
+    fn end_of_input_is_a_fault() {
+        let mut machine = shout();
+        let mut output = Vec::new();
+        let error = interact(&mut machine, "hello\n".as_bytes(), &mut output).unwrap_err();
+        match error {
+            AsciiError::Machine(fault) => {
+                assert_eq!(fault.kind, ErrorKind::InputExhausted)
+            }
+            other => panic!("expected a fault, got {:?}", other),
+        }
+        assert_eq!(output, b"> HELLO\n");
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::instruction::{mnemonic, Instruction};
+use crate::program::Program;
+use crate::{ADD, ADJ, EQU, HLT, IMMEDIATE, INP, JNZ, JZ, LES, MUL, OUT, POSITION, RELATIVE};
+
+const OPCODES: [i64; 10] = [ADD, MUL, INP, OUT, JNZ, JZ, LES, EQU, ADJ, HLT];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    UnknownMnemonic(String),
+    InvalidOperand(String),
+    InvalidLabel(String),
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    OperandCount { expected: usize, found: usize },
+    MissingDestination,
+    UnexpectedDestination,
+    ImmediateDestination,
+    AddressMismatch { expected: usize, found: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AsmErrorKind::UnknownMnemonic(name) => write!(f, "unknown mnemonic '{}'", name),
+            AsmErrorKind::InvalidOperand(text) => write!(f, "invalid operand '{}'", text),
+            AsmErrorKind::InvalidLabel(name) => write!(f, "invalid label '{}'", name),
+            AsmErrorKind::UndefinedLabel(name) => write!(f, "undefined label '{}'", name),
+            AsmErrorKind::DuplicateLabel(name) => write!(f, "duplicate label '{}'", name),
+            AsmErrorKind::OperandCount { expected, found } => {
+                write!(f, "expected {} operands, found {}", expected, found)
+            }
+            AsmErrorKind::MissingDestination => write!(f, "missing '-> destination'"),
+            AsmErrorKind::UnexpectedDestination => write!(f, "instruction has no destination"),
+            AsmErrorKind::ImmediateDestination => write!(f, "destination in immediate mode"),
+            AsmErrorKind::AddressMismatch { expected, found } => {
+                write!(f, "address {:04} expected, but at {:04}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Debug)]
+struct Operand {
+    mode: i64,
+    value: String,
+}
+
+#[derive(Debug)]
+enum Body {
+    Instruction { opcode: i64, operands: Vec<Operand> },
+    Data(Vec<String>),
+}
+
+#[derive(Debug)]
+struct Statement {
+    line: usize,
+    body: Body,
+}
+
+/// Assembles Intcode source into a program.
+///
+/// Each line holds optional `label:` definitions followed by an instruction
+/// or a `db`/`DATA` directive; `;` starts a comment. Parameters are written
+/// `[address]` (position), `#value` (immediate) or `[rb+offset]` (relative),
+/// and the destination of a writing instruction follows `->`:
+///
+/// ```text
+/// loop: ADD [counter], #1 -> [counter]
+///       LES [counter], #10 -> [rb+0]
+///       JNZ [rb+0], #loop
+///       HLT
+/// counter: db 0
+/// ```
+///
+/// Numbers and labels can be combined with `+` and `-`. A purely numeric
+/// label such as `0013:` asserts the current address instead, so the output
+/// of `disasm::listing` assembles back to the same program.
+pub fn assemble(source: &str) -> Result<Program, AsmError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = 0;
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let error = |kind| AsmError { line, kind };
+        let mut text = text.split(';').next().unwrap_or_default().trim();
+        while let Some((label, rest)) = split_label(text) {
+            if label.chars().all(|c| c.is_ascii_digit()) {
+                let expected = label
+                    .parse()
+                    .map_err(|_| error(AsmErrorKind::InvalidLabel(label.to_string())))?;
+                if expected != address {
+                    return Err(error(AsmErrorKind::AddressMismatch {
+                        expected,
+                        found: address,
+                    }));
+                }
+            } else if !is_identifier(label) || label == "rb" {
+                return Err(error(AsmErrorKind::InvalidLabel(label.to_string())));
+            } else if labels.insert(label.to_string(), address as i64).is_some() {
+                return Err(error(AsmErrorKind::DuplicateLabel(label.to_string())));
+            }
+            text = rest;
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let body = parse_statement(text).map_err(error)?;
+        address += match &body {
+            Body::Instruction { operands, .. } => 1 + operands.len(),
+            Body::Data(values) => values.len(),
+        };
+        statements.push(Statement { line, body });
+    }
+
+    let mut words = Vec::with_capacity(address);
+    for statement in statements {
+        let line = statement.line;
+        let error = |kind| AsmError { line, kind };
+        match statement.body {
+            Body::Instruction { opcode, operands } => {
+                let mut modes = [POSITION; 3];
+                for (mode, operand) in modes.iter_mut().zip(&operands) {
+                    *mode = operand.mode;
+                }
+                words.push(Instruction { opcode, modes }.encode());
+                for operand in operands {
+                    words.push(evaluate(&operand.value, &labels).map_err(error)?);
+                }
+            }
+            Body::Data(values) => {
+                for value in values {
+                    words.push(evaluate(&value, &labels).map_err(error)?);
+                }
+            }
+        }
+    }
+    Ok(Program::new(words))
+}
+
+fn split_label(text: &str) -> Option<(&str, &str)> {
+    let colon = text.find(':')?;
+    let label = &text[..colon];
+    if label.is_empty() || label.contains(|c: char| c.is_whitespace() || c == '[' || c == '#') {
+        return None;
+    }
+    Some((label, text[colon + 1..].trim_start()))
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_statement(text: &str) -> Result<Body, AsmErrorKind> {
+    let (name, rest) = match text.find(char::is_whitespace) {
+        Some(end) => (&text[..end], text[end..].trim()),
+        None => (text, ""),
+    };
+    let name = name.to_uppercase();
+    let split = |text: &str| -> Vec<String> {
+        if text.trim().is_empty() {
+            Vec::new()
+        } else {
+            text.split(',')
+                .map(|item| item.trim().to_string())
+                .collect()
+        }
+    };
+    if name == "DB" || name == "DATA" {
+        return Ok(Body::Data(split(rest)));
+    }
+    let opcode = *OPCODES
+        .iter()
+        .find(|&&opcode| mnemonic(opcode) == Some(&name))
+        .ok_or(AsmErrorKind::UnknownMnemonic(name))?;
+
+    let (sources, destination) = match rest.find("->") {
+        Some(arrow) => (&rest[..arrow], Some(rest[arrow + 2..].trim())),
+        None => (rest, None),
+    };
+    let mut operands = split(sources)
+        .iter()
+        .map(|text| parse_operand(text))
+        .collect::<Result<Vec<_>, _>>()?;
+    let shape = Instruction {
+        opcode,
+        modes: [POSITION; 3],
+    };
+    match (shape.destination(), destination) {
+        (Some(_), Some(destination)) => {
+            let destination = parse_operand(destination)?;
+            if destination.mode == IMMEDIATE {
+                return Err(AsmErrorKind::ImmediateDestination);
+            }
+            operands.push(destination);
+        }
+        (Some(_), None) => return Err(AsmErrorKind::MissingDestination),
+        (None, Some(_)) => return Err(AsmErrorKind::UnexpectedDestination),
+        (None, None) => {}
+    }
+    if operands.len() != shape.parameters() {
+        return Err(AsmErrorKind::OperandCount {
+            expected: shape.parameters(),
+            found: operands.len(),
+        });
+    }
+    Ok(Body::Instruction { opcode, operands })
+}
+
+fn parse_operand(text: &str) -> Result<Operand, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidOperand(text.to_string());
+    if let Some(value) = text.strip_prefix('#') {
+        return Ok(Operand {
+            mode: IMMEDIATE,
+            value: value.trim().to_string(),
+        });
+    }
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+        .ok_or_else(invalid)?
+        .trim();
+    match inner.strip_prefix("rb") {
+        Some(offset) if offset.trim().is_empty() => Ok(Operand {
+            mode: RELATIVE,
+            value: "0".to_string(),
+        }),
+        Some(offset) if offset.trim_start().starts_with(['+', '-']) => Ok(Operand {
+            mode: RELATIVE,
+            value: offset.trim().to_string(),
+        }),
+        _ => Ok(Operand {
+            mode: POSITION,
+            value: inner.to_string(),
+        }),
+    }
+}
+
+// Evaluates sums and differences of numbers and labels, e.g. `table+3`.
+fn evaluate(expression: &str, labels: &HashMap<String, i64>) -> Result<i64, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidOperand(expression.to_string());
+    let mut total: i64 = 0;
+    let mut sign = 1;
+    let mut term = String::new();
+    let mut terms = 0;
+    for c in expression.chars().chain(Some('+')) {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c != '+' && c != '-' {
+            term.push(c);
+            continue;
+        }
+        if term.is_empty() {
+            if c == '-' {
+                sign = -sign;
+            }
+            continue;
+        }
+        let value = if term.starts_with(|c: char| c.is_ascii_digit()) {
+            term.parse::<i64>().map_err(|_| invalid())?
+        } else if is_identifier(&term) {
+            *labels
+                .get(&term)
+                .ok_or_else(|| AsmErrorKind::UndefinedLabel(term.clone()))?
+        } else {
+            return Err(invalid());
+        };
+        total = total.checked_add(sign * value).ok_or_else(invalid)?;
+        terms += 1;
+        term.clear();
+        sign = if c == '-' { -1 } else { 1 };
+    }
+    if terms == 0 {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asm::{assemble, AsmError, AsmErrorKind};
+    use crate::disasm::listing;
+    use crate::{Machine, Program};
+
+    fn words(source: &str) -> Vec<i64> {
+        assemble(source).unwrap().into()
+    }
+
+    fn error(source: &str) -> AsmError {
+        assemble(source).unwrap_err()
+    }
+
+    #[test]
+    fn equal_to_8() {
+        let source = "
+            INP -> [input]
+            EQU [input], [eight] -> [input]
+            OUT [input]
+            HLT
+        input: db -1
+        eight: db 8
+        ";
+        assert_eq!(words(source), vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8]);
+    }
+
+    #[test]
+    fn modes_and_relative_base() {
+        let source = "
+            ADJ #1
+            OUT [rb-1]   ; the quine from day 9
+            ADD [100], #1 -> [100]
+            EQU [100], #16 -> [101]
+            JZ [101], #0
+            HLT
+        ";
+        assert_eq!(
+            words(source),
+            vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99]
+        );
+        assert_eq!(words("ADD [rb], [rb+2] -> [rb - 3]"), vec![22201, 0, 2, -3]);
+    }
+
+    #[test]
+    fn labels_and_expressions() {
+        let source = "
+        start: INP -> [table+1]
+               JNZ #1, #done
+        table: db 0, 0, start - 1
+        done:  HLT
+        ";
+        assert_eq!(words(source), vec![3, 6, 1105, 1, 8, 0, 0, -1, 99]);
+        assert_eq!(words("l1: l2: db l2, end\nend:"), vec![0, 2]);
+    }
+
+    #[test]
+    fn runs_assembled_program() {
+        let source = "
+            ADJ #counter
+        loop:
+            ADD [rb+0], #1 -> [rb+0]
+            OUT [rb+0]
+            LES [rb+0], #3 -> [flag]
+            JNZ [flag], #loop
+            hlt
+        flag: db 0
+        counter: db 0
+        ";
+        let mut machine = Machine::new(assemble(source).unwrap());
+        machine.run().unwrap();
+        assert_eq!(machine.output(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn round_trips_listings() {
+        for path in &[
+            "../aoc5/input.txt",
+            "../aoc7/input.txt",
+            "../aoc9/input.txt",
+        ] {
+            let program = Program::from_path(path).unwrap();
+            assert_eq!(assemble(&listing(program.words())).unwrap(), program);
+        }
+    }
+
+    #[test]
+    fn reports_errors_with_line() {
+        assert_eq!(
+            error("HLT\nFOO #1"),
+            AsmError {
+                line: 2,
+                kind: AsmErrorKind::UnknownMnemonic("FOO".to_string())
+            }
+        );
+        assert_eq!(
+            error("JNZ #1, #nowhere").kind,
+            AsmErrorKind::UndefinedLabel("nowhere".to_string())
+        );
+        assert_eq!(
+            error("a: HLT\na: HLT").kind,
+            AsmErrorKind::DuplicateLabel("a".to_string())
+        );
+        assert_eq!(
+            error("ADD #1, #2 -> #3").kind,
+            AsmErrorKind::ImmediateDestination
+        );
+        assert_eq!(error("ADD #1, #2").kind, AsmErrorKind::MissingDestination);
+        assert_eq!(
+            error("OUT #1 -> [2]").kind,
+            AsmErrorKind::UnexpectedDestination
+        );
+        assert_eq!(
+            error("ADD #1 -> [2]").kind,
+            AsmErrorKind::OperandCount {
+                expected: 3,
+                found: 2
+            }
+        );
+        assert_eq!(
+            error("OUT 7").kind,
+            AsmErrorKind::InvalidOperand("7".to_string())
+        );
+        assert_eq!(
+            error("HLT\n0002: HLT").to_string(),
+            "line 2: address 0002 expected, but at 0001"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cell::Cell;
+
+const BASE: u64 = 1 << 32;
+// Largest power of ten that fits in a limb, for converting to decimal.
+const DECIMAL_BASE: u32 = 1_000_000_000;
+
+/// An arbitrary-precision integer, just enough of one to be an Intcode
+/// cell: addition, multiplication, comparison and decimal text.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    // magnitude in base 2^32, least significant first, no leading zeros
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mut limbs: Vec<u32>) -> BigInt {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigInt {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let total = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        sum.push(total as u32);
+        carry = total >> 32;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+// `a - b` where `a` is at least `b`.
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let subtrahend = *b.get(i).unwrap_or(&0) as u64 + borrow;
+        if limb as u64 >= subtrahend {
+            difference.push((limb as u64 - subtrahend) as u32);
+            borrow = 0;
+        } else {
+            difference.push((BASE + limb as u64 - subtrahend) as u32);
+            borrow = 1;
+        }
+    }
+    difference
+}
+
+fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let total = x as u64 * y as u64 + product[i + j] as u64 + carry;
+            product[i + j] = total as u32;
+            carry = total >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+    product
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> BigInt {
+        let magnitude = value.unsigned_abs();
+        BigInt::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitudes(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Cell for BigInt {
+    fn from_i64(value: i64) -> BigInt {
+        value.into()
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.limbs.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0u64, |value, &limb| value << 32 | limb as u64);
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    fn try_add(&self, other: &BigInt) -> Option<BigInt> {
+        if self.negative == other.negative {
+            let limbs = add_magnitudes(&self.limbs, &other.limbs);
+            return Some(BigInt::from_parts(self.negative, limbs));
+        }
+        Some(match compare_magnitudes(&self.limbs, &other.limbs) {
+            Ordering::Less => {
+                BigInt::from_parts(other.negative, sub_magnitudes(&other.limbs, &self.limbs))
+            }
+            _ => BigInt::from_parts(self.negative, sub_magnitudes(&self.limbs, &other.limbs)),
+        })
+    }
+
+    fn try_mul(&self, other: &BigInt) -> Option<BigInt> {
+        let limbs = mul_magnitudes(&self.limbs, &other.limbs);
+        Some(BigInt::from_parts(self.negative != other.negative, limbs))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // split into base 10^9 digits, least significant first
+        let mut digits = Vec::new();
+        let mut magnitude = self.limbs.clone();
+        while !magnitude.is_empty() {
+            let mut remainder = 0u64;
+            for limb in magnitude.iter_mut().rev() {
+                let value = remainder << 32 | *limb as u64;
+                *limb = (value / DECIMAL_BASE as u64) as u32;
+                remainder = value % DECIMAL_BASE as u64;
+            }
+            digits.push(remainder);
+            while magnitude.last() == Some(&0) {
+                magnitude.pop();
+            }
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        match digits.split_last() {
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+                for digit in rest.iter().rev() {
+                    write!(f, "{:09}", digit)?;
+                }
+                Ok(())
+            }
+            None => write!(f, "0"),
+        }
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BigInt, String> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("invalid number '{}'", s));
+        }
+        let mut limbs: Vec<u32> = Vec::new();
+        for digit in digits.bytes() {
+            let mut carry = (digit - b'0') as u64;
+            for limb in limbs.iter_mut() {
+                let value = *limb as u64 * 10 + carry;
+                *limb = value as u32;
+                carry = value >> 32;
+            }
+            if carry > 0 {
+                limbs.push(carry as u32);
+            }
+        }
+        Ok(BigInt::from_parts(negative, limbs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BigInt, Cell};
+
+    fn big(text: &str) -> BigInt {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn converts_to_and_from_text() {
+        for text in &[
+            "0",
+            "7",
+            "-12",
+            "4294967296",
+            "-123456789012345678901234567890",
+        ] {
+            assert_eq!(big(text).to_string(), *text);
+        }
+        assert_eq!(big("-0"), BigInt::default());
+        assert_eq!(big("+1000000000").to_string(), "1000000000");
+        assert!("12a".parse::<BigInt>().is_err());
+        assert!("-".parse::<BigInt>().is_err());
+    }
+
+    #[test]
+    fn converts_to_and_from_i64() {
+        for &value in &[0, 1, -1, i64::MAX, i64::MIN, 1 << 32, -(1 << 40)] {
+            let converted = BigInt::from(value);
+            assert_eq!(converted.to_string(), value.to_string());
+            assert_eq!(converted.to_i64(), Some(value));
+        }
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+    }
+
+    #[test]
+    fn adds_and_multiplies() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!(
+            a.try_add(&b).unwrap().to_string(),
+            "-864197532086419753208641975320"
+        );
+        assert_eq!(
+            b.try_add(&a).unwrap(),
+            big("-864197532086419753208641975320")
+        );
+        assert_eq!(
+            a.try_add(&big("-123456789012345678901234567890")),
+            Some(BigInt::default())
+        );
+        assert_eq!(
+            a.try_mul(&b).unwrap().to_string(),
+            "-121932631137021795226185032733622923332237463801111263526900"
+        );
+        let max = BigInt::from(i64::MAX);
+        assert_eq!(
+            max.try_mul(&max).unwrap().to_string(),
+            "85070591730234615847396907784232501249"
+        );
+    }
+
+    #[test]
+    fn orders_by_value() {
+        let mut values = vec![big("5"), big("-100000000000000000000"), big("0"), big("-3")];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![big("-100000000000000000000"), big("-3"), big("0"), big("5")]
+        );
+        assert!(big("4294967296") > big("4294967295"));
+    }
+}
@@ -0,0 +1,61 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use intcode::debugger::{Command, Debugger};
+use intcode::{Machine, Program};
+
+// usage: intcode-dbg <program> [input...]
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("usage: intcode-dbg <program> [input...]");
+        process::exit(2);
+    }
+    let program = Program::from_path(&args[0]).unwrap_or_else(|error| {
+        eprintln!("{}: {}", args[0], error);
+        process::exit(1);
+    });
+    let input: Vec<i64> = args[1..]
+        .iter()
+        .map(|arg| {
+            arg.parse().unwrap_or_else(|_| {
+                eprintln!("invalid input '{}'", arg);
+                process::exit(2);
+            })
+        })
+        .collect();
+
+    let mut debugger = Debugger::new(Machine::with_input(program, &input));
+    println!("{}", debugger.location());
+    let stdin = io::stdin();
+    let mut last = None;
+    loop {
+        print!("(dbg) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        // an empty line repeats the previous step, back or continue
+        let command = if line.trim().is_empty() {
+            match last.clone() {
+                Some(command) => command,
+                None => continue,
+            }
+        } else {
+            match line.parse::<Command>() {
+                Ok(command) => command,
+                Err(message) => {
+                    println!("{} (try 'help')", message);
+                    continue;
+                }
+            }
+        };
+        if command == Command::Quit {
+            break;
+        }
+        last = Some(command.clone()).filter(Command::repeats);
+        print!("{}", debugger.execute(command));
+    }
+}
@@ -0,0 +1,253 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::num::Wrapping;
+use std::process;
+
+use intcode::calls::CallGraph;
+use intcode::decompile::decompile_from;
+use intcode::flow::Graph;
+use intcode::io::InputSource;
+use intcode::{
+    ascii, BigInt, Cell, ErrorKind, Level, Machine, Observer, Program, SelfModify, Status,
+};
+
+const USAGE: &str = "\
+usage: intcode [options] <program>
+
+Runs an Intcode program, printing each output on a line of its own. Input
+is taken from the values given with -i and -f, then from standard input.
+
+options:
+  -i, --input VALUES     queue input values, separated by commas; in ASCII
+                         mode, queue a line of text (may be repeated)
+  -f, --input-file PATH  queue the input in a file, as for -i
+  -l, --level LEVEL      restrict the instruction set to day2, day5 or day9
+                         (default day9)
+  -c, --cells TYPE       arithmetic: i64 (fault on overflow, the default),
+                         wrapping, i128 or big
+  -a, --ascii            print output as text, and read input as lines
+  -s, --self-modifying   report writes into code that has been executed,
+                         on standard error (i64 cells only)
+  -g, --graph            print the control-flow graph in Graphviz DOT
+                         instead of running the program
+      --calls            list the functions found and the calls between
+                         them instead of running the program
+      --decompile        print the program as pseudo-Rust instead of
+                         running it
+      --entry ADDRESS    also decompile from ADDRESS, for code reached
+                         through a computed jump (may be repeated)
+      --no-stdin         fail instead of reading standard input
+  -h, --help             show this help";
+
+struct Options {
+    program: String,
+    inputs: Vec<String>,
+    level: Level,
+    cells: String,
+    ascii: bool,
+    self_modifying: bool,
+    graph: bool,
+    calls: bool,
+    decompile: bool,
+    entries: Vec<usize>,
+    stdin: bool,
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("intcode: {}", message);
+    eprintln!("try 'intcode --help'");
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut args = env::args().skip(1);
+    let mut program = None;
+    let mut options = Options {
+        program: String::new(),
+        inputs: Vec::new(),
+        level: Level::default(),
+        cells: "i64".to_string(),
+        ascii: false,
+        self_modifying: false,
+        graph: false,
+        calls: false,
+        decompile: false,
+        entries: vec![0],
+        stdin: true,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .unwrap_or_else(|| usage_error(&format!("{} needs a value", name)))
+        };
+        match arg.as_str() {
+            "-i" | "--input" => options.inputs.push(value(&arg)),
+            "-f" | "--input-file" => {
+                let path = value(&arg);
+                let file = File::open(&path).unwrap_or_else(|error| {
+                    eprintln!("intcode: {}: {}", path, error);
+                    process::exit(1);
+                });
+                for line in BufReader::new(file).lines() {
+                    options.inputs.push(line.unwrap_or_else(|error| {
+                        eprintln!("intcode: {}: {}", path, error);
+                        process::exit(1);
+                    }));
+                }
+            }
+            "-l" | "--level" => {
+                options.level = value(&arg)
+                    .parse()
+                    .unwrap_or_else(|message: String| usage_error(&message))
+            }
+            "-c" | "--cells" => options.cells = value(&arg),
+            "-a" | "--ascii" => options.ascii = true,
+            "-s" | "--self-modifying" => options.self_modifying = true,
+            "-g" | "--graph" => options.graph = true,
+            "--calls" => options.calls = true,
+            "--decompile" => options.decompile = true,
+            "--entry" => {
+                let address = value(&arg);
+                let address = address
+                    .parse()
+                    .unwrap_or_else(|_| usage_error(&format!("invalid address '{}'", address)));
+                options.entries.push(address);
+            }
+            "--no-stdin" => options.stdin = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option '{}'", arg)),
+            _ if program.is_none() => program = Some(arg),
+            _ => usage_error(&format!("unexpected argument '{}'", arg)),
+        }
+    }
+    if options.ascii && options.cells != "i64" {
+        usage_error("ASCII mode needs i64 cells");
+    }
+    if options.self_modifying && options.cells != "i64" {
+        usage_error("--self-modifying needs i64 cells");
+    }
+    options.program = program.unwrap_or_else(|| usage_error("no program given"));
+    options
+}
+
+fn queue_numbers<C: Cell>(machine: &mut Machine<C>, text: &str) {
+    let words = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty());
+    for word in words {
+        let value = word
+            .parse()
+            .unwrap_or_else(|_| usage_error(&format!("invalid input '{}'", word)));
+        machine.push_input(C::from_i64(value));
+    }
+}
+
+fn run_ascii<O: Observer>(
+    program: Program,
+    options: &Options,
+    observer: &mut O,
+) -> Result<(), String> {
+    let mut machine = Machine::new(program);
+    machine.set_level(options.level);
+    options
+        .inputs
+        .iter()
+        .for_each(|line| machine.push_line(line));
+    let stdout = io::stdout().lock();
+    let result = if options.stdin {
+        ascii::interact_with(&mut machine, io::stdin().lock(), stdout, observer)
+    } else {
+        ascii::interact_with(&mut machine, io::empty(), stdout, observer)
+    };
+    result.map_err(|error| error.to_string())
+}
+
+fn run_numbers<C: Cell, O: Observer<C>>(
+    program: Program,
+    options: &Options,
+    observer: &mut O,
+) -> Result<(), String> {
+    let mut machine = Machine::<C>::from_program(program);
+    machine.set_level(options.level);
+    for text in options.inputs.iter() {
+        queue_numbers(&mut machine, text);
+    }
+    let mut stdin = intcode::io::stdin();
+    loop {
+        match machine
+            .resume_with(observer)
+            .map_err(|error| error.to_string())?
+        {
+            Status::Output(value) => {
+                machine.take_output();
+                println!("{}", value);
+            }
+            Status::NeedsInput => {
+                let value = if options.stdin {
+                    stdin.next_input()
+                } else {
+                    None
+                };
+                match value {
+                    Some(value) => machine.push_input(C::from_i64(value)),
+                    None => return Err(machine.fault(ErrorKind::InputExhausted).to_string()),
+                }
+            }
+            Status::Halted => return Ok(()),
+        }
+    }
+}
+
+fn main() {
+    let options = parse_args();
+    let program = Program::from_path(&options.program).unwrap_or_else(|error| {
+        eprintln!("intcode: {}: {}", options.program, error);
+        process::exit(1);
+    });
+    if options.decompile {
+        let words: Vec<i64> = program.into();
+        print!("{}", decompile_from(&words, &options.entries));
+        return;
+    }
+    if options.graph || options.calls {
+        let words: Vec<i64> = program.into();
+        let graph = Graph::build(&words);
+        if options.graph {
+            print!("{}", graph.to_dot());
+        } else {
+            print!("{}", CallGraph::new(&graph).report());
+        }
+        return;
+    }
+
+    let result = match options.cells.as_str() {
+        _ if options.self_modifying => {
+            let mut detector = SelfModify::new();
+            let result = if options.ascii {
+                run_ascii(program, &options, &mut detector)
+            } else {
+                run_numbers(program, &options, &mut detector)
+            };
+            eprint!("{}", detector.report());
+            result
+        }
+        _ if options.ascii => run_ascii(program, &options, &mut ()),
+        "i64" => run_numbers::<i64, _>(program, &options, &mut ()),
+        "wrapping" => run_numbers::<Wrapping<i64>, _>(program, &options, &mut ()),
+        "i128" => run_numbers::<i128, _>(program, &options, &mut ()),
+        "big" => run_numbers::<BigInt, _>(program, &options, &mut ()),
+        other => usage_error(&format!(
+            "unknown cell type '{}', expected i64, wrapping, i128 or big",
+            other
+        )),
+    };
+
+    if let Err(error) = result {
+        eprintln!("intcode: {}: {}", options.program, error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+
+use crate::cell::Cell;
+use crate::instruction::Instruction;
+use crate::memory::Memory;
+use crate::{IMMEDIATE, POSITION, RELATIVE};
+
+/// A parameter with its mode and word already decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Param {
+    Position(i64),
+    /// The value is the parameter word itself, at the address given.
+    Immediate(usize),
+    Relative(i64),
+    /// A position or relative word that does not fit in an i64.
+    Overflow,
+    UnknownMode(i64),
+}
+
+// The mode of a `Param`, kept apart from its word so that an `Op` packs
+// into 32 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Position,
+    Immediate,
+    Relative,
+    Overflow,
+    UnknownMode,
+}
+
+/// An instruction decoded once, for executing again without redoing the
+/// divisions, or reading its parameter words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Op {
+    opcode: i8,
+    // the modes observers are shown, before the level checks them
+    fetched: [i8; 3],
+    kinds: [Kind; 3],
+    words: [i64; 3],
+}
+
+impl Op {
+    /// Decodes the parameters of `instruction` at `pc` in `memory`.
+    pub fn new<C: Cell>(
+        fetched: Instruction,
+        instruction: Instruction,
+        pc: usize,
+        memory: &Memory<C>,
+    ) -> Op {
+        let mut op = Op {
+            // a word's last two digits and its mode digits always fit
+            opcode: fetched.opcode as i8,
+            fetched: [
+                fetched.modes[0] as i8,
+                fetched.modes[1] as i8,
+                fetched.modes[2] as i8,
+            ],
+            kinds: [Kind::Immediate; 3],
+            words: [0; 3],
+        };
+        for index in 0..instruction.parameters() {
+            let address = pc + index + 1;
+            let word = memory[address].to_i64();
+            let (kind, word) = match (instruction.modes[index], word) {
+                (IMMEDIATE, _) => (Kind::Immediate, address as i64),
+                (POSITION, Some(word)) => (Kind::Position, word),
+                (RELATIVE, Some(word)) => (Kind::Relative, word),
+                (POSITION, None) | (RELATIVE, None) => (Kind::Overflow, 0),
+                (mode, _) => (Kind::UnknownMode, mode),
+            };
+            op.kinds[index] = kind;
+            op.words[index] = word;
+        }
+        op
+    }
+
+    pub fn opcode(&self) -> i64 {
+        self.opcode as i64
+    }
+
+    /// The instruction as fetched, for observers.
+    pub fn fetched(&self) -> Instruction {
+        Instruction {
+            opcode: self.opcode as i64,
+            modes: [
+                self.fetched[0] as i64,
+                self.fetched[1] as i64,
+                self.fetched[2] as i64,
+            ],
+        }
+    }
+
+    pub fn params(&self) -> [Param; 3] {
+        let param = |index: usize| {
+            let word = self.words[index];
+            match self.kinds[index] {
+                Kind::Position => Param::Position(word),
+                Kind::Immediate => Param::Immediate(word as usize),
+                Kind::Relative => Param::Relative(word),
+                Kind::Overflow => Param::Overflow,
+                Kind::UnknownMode => Param::UnknownMode(word),
+            }
+        };
+        [param(0), param(1), param(2)]
+    }
+}
+
+// Addresses up to this far before a write may hold an instruction covering it.
+const LONGEST: usize = 4;
+
+/// Decoded instructions by address. Every write into memory must be passed
+/// to `invalidate`, so that an instruction is decoded again once any of its
+/// words changes.
+///
+/// Only the flat region of memory is cached, and only as far as the last
+/// instruction executed; instructions far away are decoded every time. Two
+/// caches always compare equal, as they only hold what memory already says.
+#[derive(Clone, Default)]
+pub(crate) struct Cache {
+    ops: Vec<Option<Op>>,
+    // decodes every instruction afresh, for comparing against
+    #[cfg(test)]
+    pub disabled: bool,
+}
+
+impl Cache {
+    pub fn get(&self, pc: usize) -> Option<Op> {
+        self.ops.get(pc).copied().flatten()
+    }
+
+    /// Keeps `op` for `pc`, if `pc` is in the first `limit` addresses.
+    pub fn insert(&mut self, pc: usize, op: Op, limit: usize) {
+        #[cfg(test)]
+        {
+            if self.disabled {
+                return;
+            }
+        }
+        if pc >= limit {
+            return;
+        }
+        if pc >= self.ops.len() {
+            self.ops.resize(pc + 1, None);
+        }
+        self.ops[pc] = Some(op);
+    }
+
+    pub fn invalidate(&mut self, address: usize) {
+        let end = (address + 1).min(self.ops.len());
+        let start = (address + 1).saturating_sub(LONGEST).min(end);
+        for op in self.ops[start..end].iter_mut() {
+            *op = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+}
+
+impl PartialEq for Cache {
+    fn eq(&self, _other: &Cache) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decoded = self.ops.iter().filter(|op| op.is_some()).count();
+        write!(f, "Cache {{ decoded: {} }}", decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{Cache, Op, Param};
+    use crate::instruction::Instruction;
+    use crate::Memory;
+
+    #[test]
+    fn decodes_parameters() {
+        let memory = Memory::new(vec![21101, 7, -1, 5, 1, 9]);
+        let instruction = Instruction::decode(memory[0]);
+        let op = Op::new(instruction, instruction, 0, &memory);
+        assert_eq!(
+            op.params(),
+            [Param::Immediate(1), Param::Immediate(2), Param::Relative(5)]
+        );
+        let instruction = Instruction::decode(40001);
+        let op = Op::new(instruction, instruction, 4, &memory);
+        assert_eq!(op.fetched(), instruction);
+        assert_eq!(
+            op.params(),
+            [
+                Param::Position(9),
+                Param::Position(0),
+                Param::UnknownMode(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_invalidate_covering_instructions() {
+        let op = Op::new(
+            Instruction::decode(99),
+            Instruction::decode(99),
+            0,
+            &Memory::new(vec![99]),
+        );
+        let mut cache = Cache::default();
+        for pc in [0, 2, 5, 6, 9] {
+            cache.insert(pc, op, 8);
+        }
+        assert_eq!(cache.get(9), None);
+        // grown only as far as needed
+        assert_eq!(cache.ops.len(), 7);
+        assert_eq!(std::mem::size_of::<Option<Op>>(), 32);
+        cache.invalidate(5);
+        assert_eq!(cache.get(0), Some(op));
+        assert_eq!(
+            (cache.get(2), cache.get(5), cache.get(6)),
+            (None, None, Some(op))
+        );
+        cache.invalidate(100);
+        cache.invalidate(1);
+        assert_eq!(cache.get(0), None);
+    }
+}
@@ -0,0 +1,620 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+use crate::disasm::Line;
+use crate::flow::{Edge, EdgeKind, Exit, Graph};
+use crate::instruction::Instruction;
+use crate::observer::Observer;
+use crate::{ADJ, IMMEDIATE, RELATIVE};
+
+// Programs built by the usual compiler pass arguments on the stack: the
+// caller stores them at [rb+1], [rb+2]..., the return address at [rb+0],
+// then jumps. The callee moves the relative base past its frame with
+// `ADJ #n`, finds its arguments at [rb-n+1]..., and returns with `ADJ #-n`
+// and `JZ #0, [rb+0]`, leaving results where its arguments were.
+//
+// Slots below are numbered from the relative base at the call, so slot 0
+// is the return address and arguments start at slot 1.
+
+/// A jump to a function, see `flow::EdgeKind::Call`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallSite {
+    /// The jump.
+    pub address: usize,
+    /// Entry of the function making the call.
+    pub caller: usize,
+    pub callee: usize,
+    pub return_site: usize,
+    /// Slots stored by the calling block before the jump.
+    pub arguments: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub entry: usize,
+    /// How far the entry moves the relative base, 0 for code that keeps
+    /// the caller's.
+    pub frame_size: i64,
+    /// Slots the function may read before writing them.
+    pub arguments: Vec<i64>,
+    /// Slots read by callers after the function returns.
+    pub results: Vec<i64>,
+    /// Starts of the blocks of the function, not counting those of the
+    /// functions it calls.
+    pub blocks: Vec<usize>,
+    /// Addresses of the jumps through the return address.
+    pub returns: Vec<usize>,
+    /// Addresses of the calls made by the function.
+    pub calls: Vec<usize>,
+}
+
+/// The functions of a program and which calls which, recovered from its
+/// control-flow graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraph {
+    functions: BTreeMap<usize, Function>,
+    calls: BTreeMap<usize, CallSite>,
+}
+
+// Slots an instruction reads and writes through the relative base, given
+// how far the base has moved since the function was entered. A slot past
+// the range of `i64` is not known and left out.
+pub(crate) fn slots(line: &Line, instruction: &Instruction, moved: i64) -> (Vec<i64>, Option<i64>) {
+    let mut reads = Vec::new();
+    let mut write = None;
+    for index in 0..instruction.parameters() {
+        if instruction.modes[index] != RELATIVE {
+            continue;
+        }
+        let slot = match moved.checked_add(line.words[index + 1]) {
+            Some(slot) => slot,
+            None => continue,
+        };
+        if instruction.destination() == Some(index) {
+            write = Some(slot);
+        } else {
+            reads.push(slot);
+        }
+    }
+    (reads, write)
+}
+
+// How far an instruction moves the relative base, `None` if not known.
+pub(crate) fn adjustment(line: &Line, instruction: &Instruction) -> Option<i64> {
+    if instruction.opcode != ADJ {
+        Some(0)
+    } else if instruction.modes[0] == IMMEDIATE {
+        Some(line.words[1])
+    } else {
+        None
+    }
+}
+
+// How far the base has moved after `lines`, from `moved` before them.
+pub(crate) fn moved_after(lines: &[Line], mut moved: Option<i64>) -> Option<i64> {
+    for line in lines.iter() {
+        let instruction = line.instruction?;
+        moved = moved?.checked_add(adjustment(line, &instruction)?);
+    }
+    moved
+}
+
+// Edges that stay within a function: a call continues at its return site.
+pub(crate) fn local(edge: &Edge) -> bool {
+    edge.kind != EdgeKind::Call
+}
+
+// Slots first accessed by reading them, from the base at the start of
+// `lines`.
+fn reads_before_writes(lines: &[Line]) -> Vec<i64> {
+    let mut moved = 0;
+    let mut written = BTreeSet::new();
+    let mut read = Vec::new();
+    for line in lines.iter() {
+        let instruction = match line.instruction {
+            Some(instruction) => instruction,
+            None => break,
+        };
+        let (reads, write) = slots(line, &instruction, moved);
+        read.extend(reads.into_iter().filter(|slot| !written.contains(slot)));
+        written.extend(write);
+        moved = match adjustment(line, &instruction).and_then(|n| moved.checked_add(n)) {
+            Some(moved) => moved,
+            None => break,
+        };
+    }
+    read
+}
+
+/// The blocks of the function entered at `entry`, each with how far the
+/// base has moved at its start, `None` past an `ADJ` that is not immediate.
+pub(crate) fn moved_by_block(graph: &Graph, entry: usize) -> BTreeMap<usize, Option<i64>> {
+    let mut moved = BTreeMap::new();
+    let mut pending = vec![(entry, Some(0))];
+    while let Some((start, at_start)) = pending.pop() {
+        if moved.contains_key(&start) {
+            continue;
+        }
+        let block = match graph.block(start) {
+            Some(block) => block,
+            None => continue,
+        };
+        moved.insert(start, at_start);
+        let at_end = moved_after(&block.lines, at_start);
+        for edge in block.successors.iter().filter(|edge| local(edge)) {
+            pending.push((edge.target, at_end));
+        }
+    }
+    moved
+}
+
+impl CallGraph {
+    pub fn build(words: &[i64]) -> CallGraph {
+        CallGraph::new(&Graph::build(words))
+    }
+
+    /// Treats the entries of `graph` and the targets of its calls as
+    /// functions.
+    pub fn new(graph: &Graph) -> CallGraph {
+        let mut entries: BTreeSet<usize> = graph.entries().iter().copied().collect();
+        for block in graph.blocks() {
+            for edge in block.successors.iter() {
+                if edge.kind == EdgeKind::Call {
+                    entries.insert(edge.target);
+                }
+            }
+        }
+
+        let mut functions = BTreeMap::new();
+        let mut calls = BTreeMap::new();
+        for &entry in entries.iter() {
+            if graph.block(entry).is_none() {
+                continue;
+            }
+            let moved = moved_by_block(graph, entry);
+
+            let frame_size = graph
+                .block(entry)
+                .and_then(|block| {
+                    let line = block.lines.first()?;
+                    let instruction = line.instruction?;
+                    Some(adjustment(line, &instruction)?).filter(|&n| n > 0)
+                })
+                .unwrap_or(0);
+            let mut function = Function {
+                entry,
+                frame_size,
+                arguments: Vec::new(),
+                results: Vec::new(),
+                blocks: moved.keys().copied().collect(),
+                returns: Vec::new(),
+                calls: Vec::new(),
+            };
+
+            // slots certainly written on entry to each block
+            let mut written: BTreeMap<usize, BTreeSet<i64>> = BTreeMap::new();
+            written.insert(entry, BTreeSet::new());
+            let mut arguments = BTreeSet::new();
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for (&start, &at_start) in moved.iter() {
+                    let (block, mut now) = match (graph.block(start), at_start) {
+                        (Some(block), Some(now)) => (block, now),
+                        _ => continue,
+                    };
+                    let mut slots_written = match written.get(&start) {
+                        Some(slots) => slots.clone(),
+                        None => continue,
+                    };
+                    for line in block.lines.iter() {
+                        let instruction = match line.instruction {
+                            Some(instruction) => instruction,
+                            None => break,
+                        };
+                        let (reads, write) = slots(line, &instruction, now);
+                        for slot in reads {
+                            if slot > 0 && slot < frame_size && !slots_written.contains(&slot) {
+                                arguments.insert(slot);
+                            }
+                        }
+                        slots_written.extend(write);
+                        now = match adjustment(line, &instruction).and_then(|n| now.checked_add(n))
+                        {
+                            Some(now) => now,
+                            None => break,
+                        };
+                    }
+                    for edge in block.successors.iter().filter(|edge| local(edge)) {
+                        let next = match written.get(&edge.target) {
+                            Some(slots) => slots.intersection(&slots_written).copied().collect(),
+                            None => slots_written.clone(),
+                        };
+                        if written.get(&edge.target) != Some(&next) {
+                            written.insert(edge.target, next);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            function.arguments = arguments.into_iter().collect();
+
+            for (&start, &at_start) in moved.iter() {
+                let block = match graph.block(start) {
+                    Some(block) => block,
+                    None => continue,
+                };
+                let last = block.last();
+                let instruction = match last.instruction {
+                    Some(instruction) => instruction,
+                    None => continue,
+                };
+                // moved by the end of the block, before its last instruction
+                let at_last = moved_after(&block.lines[..block.lines.len() - 1], at_start);
+                if block.exit == Exit::Computed
+                    && instruction.modes[1] == RELATIVE
+                    && at_last.and_then(|moved| moved.checked_add(last.words[2])) == Some(0)
+                {
+                    function.returns.push(last.address);
+                }
+                let callee = block
+                    .successors
+                    .iter()
+                    .find(|edge| edge.kind == EdgeKind::Call);
+                let return_site = block
+                    .successors
+                    .iter()
+                    .find(|edge| edge.kind == EdgeKind::AfterCall);
+                if let (Some(callee), Some(return_site)) = (callee, return_site) {
+                    let mut arguments = BTreeSet::new();
+                    let mut now = 0;
+                    for line in block.lines.iter() {
+                        let instruction = match line.instruction {
+                            Some(instruction) => instruction,
+                            None => break,
+                        };
+                        let (_, write) = slots(line, &instruction, now);
+                        arguments.extend(write);
+                        let adjusted = adjustment(line, &instruction).unwrap_or(0);
+                        now = now.checked_add(adjusted).unwrap_or(now);
+                    }
+                    // relative to the base at the call
+                    let arguments = arguments
+                        .into_iter()
+                        .filter_map(|slot| slot.checked_sub(now))
+                        .filter(|&slot| slot > 0)
+                        .collect();
+                    function.calls.push(last.address);
+                    calls.insert(
+                        last.address,
+                        CallSite {
+                            address: last.address,
+                            caller: entry,
+                            callee: callee.target,
+                            return_site: return_site.target,
+                            arguments,
+                        },
+                    );
+                }
+            }
+            functions.insert(entry, function);
+        }
+
+        // what callers read back from the frame of the callee
+        for call in calls.values() {
+            let after = match graph.block(call.return_site) {
+                Some(block) => block,
+                None => continue,
+            };
+            let results = reads_before_writes(&after.lines);
+            if let Some(callee) = functions.get_mut(&call.callee) {
+                for slot in results.into_iter().filter(|&slot| slot > 0) {
+                    if !callee.results.contains(&slot) {
+                        callee.results.push(slot);
+                    }
+                }
+                callee.results.sort_unstable();
+            }
+        }
+        CallGraph { functions, calls }
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.functions.values()
+    }
+
+    /// The function entered at `entry`.
+    pub fn function(&self, entry: usize) -> Option<&Function> {
+        self.functions.get(&entry)
+    }
+
+    pub fn calls(&self) -> impl Iterator<Item = &CallSite> {
+        self.calls.values()
+    }
+
+    /// The call made by the jump at `address`.
+    pub fn call_at(&self, address: usize) -> Option<&CallSite> {
+        self.calls.get(&address)
+    }
+
+    /// Entries of the functions called by the one at `entry`.
+    pub fn callees(&self, entry: usize) -> Vec<usize> {
+        let callees: BTreeSet<usize> = self
+            .calls()
+            .filter(|call| call.caller == entry)
+            .map(|call| call.callee)
+            .collect();
+        callees.into_iter().collect()
+    }
+
+    /// One paragraph per function.
+    pub fn report(&self) -> String {
+        let slots = |slots: &[i64]| {
+            slots
+                .iter()
+                .map(|slot| format!("[rb+{}]", slot))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let addresses = |addresses: &[usize]| {
+            addresses
+                .iter()
+                .map(|address| format!("{:04}", address))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut text = String::new();
+        for function in self.functions() {
+            writeln!(
+                text,
+                "{:04}: frame {}, {} blocks",
+                function.entry,
+                function.frame_size,
+                function.blocks.len()
+            )
+            .unwrap();
+            if !function.arguments.is_empty() {
+                writeln!(text, "  arguments {}", slots(&function.arguments)).unwrap();
+            }
+            if !function.results.is_empty() {
+                writeln!(text, "  results {}", slots(&function.results)).unwrap();
+            }
+            if !function.returns.is_empty() {
+                writeln!(text, "  returns at {}", addresses(&function.returns)).unwrap();
+            }
+            for call in function
+                .calls
+                .iter()
+                .filter_map(|&address| self.call_at(address))
+            {
+                writeln!(
+                    text,
+                    "  calls {:04} at {:04}, returning to {:04}",
+                    call.callee, call.address, call.return_site
+                )
+                .unwrap();
+            }
+        }
+        text
+    }
+
+    /// The call graph in Graphviz DOT, one edge per call site.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph calls {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        for function in self.functions() {
+            writeln!(
+                dot,
+                "    f{} [label=\"{:04}\\nframe {}\"];",
+                function.entry, function.entry, function.frame_size
+            )
+            .unwrap();
+        }
+        for call in self.calls() {
+            writeln!(
+                dot,
+                "    f{} -> f{} [label=\"{:04}\"];",
+                call.caller, call.callee, call.address
+            )
+            .unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// A call in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub function: usize,
+    pub call_site: usize,
+    pub return_site: usize,
+    /// The relative base at the call: the arguments are just above it.
+    pub relative_base: i64,
+}
+
+/// The calls of a call graph in progress as a machine runs. A frame is
+/// pushed when one of the calls jumps to its callee and popped on the jump
+/// to its return site; the number of calls and the deepest nesting are
+/// kept too.
+#[derive(Debug, Clone, Default)]
+pub struct CallStack {
+    calls: HashMap<usize, (usize, usize)>,
+    frames: Vec<Frame>,
+    relative_base: i64,
+    // the last instruction fetched and the one after it
+    last: Option<(usize, usize)>,
+    count: u64,
+    max_depth: usize,
+}
+
+impl<C> Observer<C> for CallStack {
+    fn fetch(&mut self, pc: usize, instruction: &Instruction) {
+        if let Some((last, next)) = self.last {
+            match self.calls.get(&last) {
+                Some(&(callee, return_site)) if callee == pc => {
+                    self.frames.push(Frame {
+                        function: callee,
+                        call_site: last,
+                        return_site,
+                        relative_base: self.relative_base,
+                    });
+                    self.count += 1;
+                    self.max_depth = self.max_depth.max(self.frames.len());
+                }
+                _ if pc != next
+                    && self.frames.last().map(|frame| frame.return_site) == Some(pc) =>
+                {
+                    self.frames.pop();
+                }
+                _ => {}
+            }
+        }
+        self.last = Some((pc, pc + instruction.size()));
+    }
+
+    fn adjust(&mut self, _old: i64, new: i64) {
+        self.relative_base = new;
+    }
+}
+
+impl CallStack {
+    /// Starts outside any call, with the relative base at 0.
+    pub fn new(graph: &CallGraph) -> CallStack {
+        CallStack {
+            calls: graph
+                .calls()
+                .map(|call| (call.address, (call.callee, call.return_site)))
+                .collect(),
+            ..CallStack::default()
+        }
+    }
+
+    /// The calls in progress, innermost last.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The deepest the stack has been.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// How many calls have been made.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// One line per frame, innermost first.
+    pub fn backtrace(&self) -> String {
+        let mut text = String::new();
+        for (depth, frame) in self.frames.iter().rev().enumerate() {
+            writeln!(
+                text,
+                "#{} {:04} called from {:04}, returns to {:04}, rb {}",
+                depth, frame.function, frame.call_site, frame.return_site, frame.relative_base
+            )
+            .unwrap();
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calls::{CallGraph, CallStack, Frame};
+    use crate::{Machine, Program, Status};
+
+    fn day9() -> Vec<i64> {
+        Program::from_path("../aoc9/input.txt").unwrap().into()
+    }
+
+    #[test]
+    fn recovers_day9_functions() {
+        let graph = CallGraph::build(&day9());
+        let entries: Vec<usize> = graph.functions().map(|function| function.entry).collect();
+        assert_eq!(entries, vec![0, 922]);
+        assert_eq!(graph.callees(0), vec![922]);
+        assert_eq!(graph.callees(922), vec![922]);
+
+        let function = graph.function(922).unwrap();
+        assert_eq!(function.frame_size, 3);
+        // [rb-2] once inside, where [rb-1] is a local
+        assert_eq!(function.arguments, vec![1]);
+        assert_eq!(function.results, vec![1]);
+        assert_eq!(function.blocks, vec![922, 931, 942, 957, 964, 968]);
+        assert_eq!(function.returns, vec![970]);
+        assert_eq!(function.calls, vec![939, 954]);
+
+        let call = graph.call_at(912).unwrap();
+        assert_eq!((call.caller, call.callee, call.return_site), (0, 922, 915));
+        assert_eq!(call.arguments, vec![1]);
+        assert_eq!(graph.call_at(954).unwrap().arguments, vec![1]);
+        assert!(graph.function(0).unwrap().returns.is_empty());
+
+        assert!(graph.report().contains(
+            "0922: frame 3, 6 blocks\n  arguments [rb+1]\n  results [rb+1]\n  \
+             returns at 0970\n  calls 0922 at 0939, returning to 0942\n"
+        ));
+        assert!(graph
+            .to_dot()
+            .contains("    f922 -> f922 [label=\"0954\"];\n"));
+    }
+
+    #[test]
+    fn follows_day9_calls() {
+        let graph = CallGraph::build(&day9());
+        let mut machine = Machine::with_input(day9(), &[2]);
+        let mut stack = CallStack::new(&graph);
+
+        // step into the first few recursive calls, the last entered with
+        // its frame already set up
+        while stack.depth() < 3 {
+            assert_eq!(machine.step_with(&mut stack).unwrap(), None);
+        }
+        assert_eq!(machine.relative_base(), 1009);
+        assert_eq!(
+            stack.frames()[..2],
+            [
+                Frame {
+                    function: 922,
+                    call_site: 912,
+                    return_site: 915,
+                    relative_base: 1000,
+                },
+                Frame {
+                    function: 922,
+                    call_site: 939,
+                    return_site: 942,
+                    relative_base: 1003,
+                },
+            ]
+        );
+        assert!(stack
+            .backtrace()
+            .ends_with("#2 0922 called from 0912, returns to 0915, rb 1000\n"));
+
+        assert_eq!(
+            machine.resume_with(&mut stack).unwrap(),
+            Status::Output(68938)
+        );
+        assert_eq!(stack.depth(), 0);
+        assert_eq!(stack.max_depth(), 26);
+        assert!(stack.count() > 1000);
+    }
+
+    #[test]
+    fn slots_past_the_range_of_i64_are_unknown() {
+        let min = i64::MIN;
+        let program = [
+            109, -44007, 1, 1206, 2, 208, 20207, min, 2, 20105, 1, -2, 5, min, 19, 16, min, 4,
+        ];
+        let calls = CallGraph::build(&program);
+        assert_eq!(calls.function(0).unwrap().frame_size, 0);
+    }
+}
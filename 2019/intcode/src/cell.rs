@@ -0,0 +1,108 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::Wrapping;
+
+/// The type of a memory cell, which decides what `ADD` and `MUL` do with
+/// results that do not fit:
+///
+/// - `i64` and `i128` fault with `Overflow`,
+/// - `Wrapping<i64>` wraps around,
+/// - `BigInt` never overflows.
+///
+/// Opcodes, addresses, jump targets and relative base adjustments must
+/// still fit in an `i64`.
+pub trait Cell: Clone + Default + PartialEq + PartialOrd + fmt::Debug + fmt::Display {
+    fn from_i64(value: i64) -> Self;
+
+    /// The value as an `i64`, if it fits.
+    fn to_i64(&self) -> Option<i64>;
+
+    /// The sum, or `None` on overflow.
+    fn try_add(&self, other: &Self) -> Option<Self>;
+
+    /// The product, or `None` on overflow.
+    fn try_mul(&self, other: &Self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Cell for i64 {
+    fn from_i64(value: i64) -> i64 {
+        value
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        Some(*self)
+    }
+
+    fn try_add(&self, other: &i64) -> Option<i64> {
+        self.checked_add(*other)
+    }
+
+    fn try_mul(&self, other: &i64) -> Option<i64> {
+        self.checked_mul(*other)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+}
+
+impl Cell for Wrapping<i64> {
+    fn from_i64(value: i64) -> Wrapping<i64> {
+        Wrapping(value)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        Some(self.0)
+    }
+
+    fn try_add(&self, other: &Wrapping<i64>) -> Option<Wrapping<i64>> {
+        Some(*self + *other)
+    }
+
+    fn try_mul(&self, other: &Wrapping<i64>) -> Option<Wrapping<i64>> {
+        Some(*self * *other)
+    }
+}
+
+impl Cell for i128 {
+    fn from_i64(value: i64) -> i128 {
+        value.into()
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        i64::try_from(*self).ok()
+    }
+
+    fn try_add(&self, other: &i128) -> Option<i128> {
+        self.checked_add(*other)
+    }
+
+    fn try_mul(&self, other: &i128) -> Option<i128> {
+        self.checked_mul(*other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::Wrapping;
+
+    use crate::cell::Cell;
+
+    #[test]
+    fn overflow_semantics() {
+        assert_eq!(i64::MAX.try_add(&1), None);
+        assert_eq!(3i64.try_mul(&4), Some(12));
+        assert_eq!(
+            Wrapping(i64::MAX).try_add(&Wrapping(1)),
+            Some(Wrapping(i64::MIN))
+        );
+        let big = i128::from(i64::MAX);
+        assert_eq!(big.try_mul(&big), Some(big * big));
+        assert_eq!((big * big).to_i64(), None);
+        assert_eq!(i128::MAX.try_add(&1), None);
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::disasm::{decode_memory, Line};
+use crate::history::History;
+use crate::machine::{Machine, Status};
+use crate::observer::Observer;
+
+pub const HELP: &str = "\
+step [n]          execute n instructions (default 1)
+back [n]          step n instructions backwards (default 1)
+back-write <addr> rewind to before the last write to addr
+back-output <n>   rewind to before output n (counting from 0) was produced
+continue          run until a breakpoint, watchpoint, input request or halt
+break <addr>      stop when the pc reaches addr
+delete <addr>     remove a breakpoint
+watch <addr>      stop after addr is written
+unwatch <addr>    remove a watchpoint
+x <addr> [n]      show n words of memory (default 8, at most 1024)
+set <addr> <val>  write val to memory
+input <val>...    queue input values
+list [addr] [n]   disassemble n instructions (default 10) from addr or the pc
+save <file>       write a snapshot of the machine
+load <file>       replace the machine with a saved snapshot
+info              show pc, relative base, breakpoints and watchpoints
+help              show this text
+quit              leave the debugger
+";
+
+// The most words `x` shows at once.
+const EXAMINE_LIMIT: usize = 1024;
+
+/// A debugger command, parsed from one line such as `b 42` or `x 100 4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Step(usize),
+    Back(usize),
+    BackToWrite(usize),
+    BackToOutput(usize),
+    Continue,
+    Break(usize),
+    Delete(usize),
+    Watch(usize),
+    Unwatch(usize),
+    Examine(usize, usize),
+    Set(usize, i64),
+    Input(Vec<i64>),
+    List(Option<usize>, usize),
+    Save(String),
+    Load(String),
+    Info,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or("empty command")?;
+        let args: Vec<&str> = words.collect();
+        let parse = |arg: &str| -> Result<i64, String> {
+            arg.parse().map_err(|_| format!("invalid number '{}'", arg))
+        };
+        let number = |index: usize| args.get(index).map(|arg| parse(arg)).transpose();
+        let address = |index: usize| -> Result<Option<usize>, String> {
+            match number(index)? {
+                Some(value) if value < 0 => Err(format!("invalid address '{}'", value)),
+                value => Ok(value.map(|value| value as usize)),
+            }
+        };
+        let required = |index: usize| address(index)?.ok_or(format!("{} needs an address", name));
+        let command = match name {
+            "s" | "step" => Command::Step(address(0)?.unwrap_or(1)),
+            "back" => Command::Back(address(0)?.unwrap_or(1)),
+            "back-write" => Command::BackToWrite(required(0)?),
+            "back-output" => {
+                Command::BackToOutput(address(0)?.ok_or("back-output needs an output index")?)
+            }
+            "c" | "continue" => Command::Continue,
+            "b" | "break" => Command::Break(required(0)?),
+            "d" | "delete" => Command::Delete(required(0)?),
+            "w" | "watch" => Command::Watch(required(0)?),
+            "unwatch" => Command::Unwatch(required(0)?),
+            "x" => Command::Examine(required(0)?, address(1)?.unwrap_or(8)),
+            "set" => Command::Set(required(0)?, number(1)?.ok_or("set needs a value")?),
+            "i" | "input" => Command::Input(
+                args.iter()
+                    .map(|arg| parse(arg))
+                    .collect::<Result<_, _>>()?,
+            ),
+            "l" | "list" => Command::List(address(0)?, address(1)?.unwrap_or(10)),
+            "save" | "load" => {
+                let path = args.join(" ");
+                if path.is_empty() {
+                    return Err(format!("{} needs a file name", name));
+                }
+                if name == "save" {
+                    Command::Save(path)
+                } else {
+                    Command::Load(path)
+                }
+            }
+            "info" => Command::Info,
+            "h" | "help" => Command::Help,
+            "q" | "quit" => Command::Quit,
+            _ => return Err(format!("unknown command '{}'", name)),
+        };
+        Ok(command)
+    }
+}
+
+impl Command {
+    /// Whether an empty line runs the command again: only the ones that
+    /// move through the program do.
+    pub fn repeats(&self) -> bool {
+        matches!(
+            self,
+            Command::Step(_) | Command::Back(_) | Command::Continue
+        )
+    }
+}
+
+// Remembers the first write to a watched address during a step.
+struct Watch<'a> {
+    addresses: &'a BTreeSet<usize>,
+    hit: Option<(usize, i64, i64)>,
+}
+
+impl Observer for Watch<'_> {
+    fn write(&mut self, address: usize, old: i64, new: i64) {
+        if self.hit.is_none() && self.addresses.contains(&address) {
+            self.hit = Some((address, old, new));
+        }
+    }
+}
+
+/// A machine under the control of breakpoints and watchpoints. Commands
+/// return the text to show the user. Everything the machine executes is
+/// recorded, so it can also be stepped backwards.
+pub struct Debugger {
+    machine: Machine,
+    history: History,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<usize>,
+}
+
+impl Debugger {
+    pub fn new(machine: Machine) -> Debugger {
+        Debugger {
+            machine,
+            history: History::default(),
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// The instruction at `address` as the machine would decode it now.
+    pub fn line_at(&self, address: usize) -> Line {
+        decode_memory(self.machine.memory(), address)
+    }
+
+    /// The current instruction and relative base, e.g.
+    /// `0004: ADD [224], #-672 -> [224]  (rb 0)`.
+    pub fn location(&self) -> String {
+        format!(
+            "{}  (rb {})",
+            self.line_at(self.machine.pc()),
+            self.machine.relative_base()
+        )
+    }
+
+    pub fn execute(&mut self, command: Command) -> String {
+        let mut text = String::new();
+        match command {
+            Command::Step(count) => return self.advance(Some(count)),
+            Command::Continue => return self.advance(None),
+            Command::Back(count) => {
+                let steps = (0..count)
+                    .take_while(|_| self.history.step_back(&mut self.machine))
+                    .count();
+                if steps < count {
+                    writeln!(text, "start of history").unwrap();
+                }
+                writeln!(text, "{}", self.location()).unwrap();
+            }
+            Command::BackToWrite(address) => {
+                if !self.history.back_to_write(&mut self.machine, address) {
+                    writeln!(text, "no write to [{}] in history", address).unwrap();
+                }
+                writeln!(text, "{}", self.location()).unwrap();
+            }
+            Command::BackToOutput(index) => {
+                if !self.history.back_to_output(&mut self.machine, index) {
+                    writeln!(text, "output {} not in history", index).unwrap();
+                }
+                writeln!(text, "{}", self.location()).unwrap();
+            }
+            Command::Break(address) => {
+                self.breakpoints.insert(address);
+                writeln!(text, "breakpoint at {:04}", address).unwrap();
+            }
+            Command::Delete(address) => {
+                if !self.breakpoints.remove(&address) {
+                    writeln!(text, "no breakpoint at {:04}", address).unwrap();
+                }
+            }
+            Command::Watch(address) => {
+                self.watchpoints.insert(address);
+                writeln!(text, "watching [{}]", address).unwrap();
+            }
+            Command::Unwatch(address) => {
+                if !self.watchpoints.remove(&address) {
+                    writeln!(text, "not watching [{}]", address).unwrap();
+                }
+            }
+            Command::Examine(address, count) => {
+                let memory = self.machine.memory();
+                let end = address.saturating_add(count.min(EXAMINE_LIMIT));
+                for row in (address..end).step_by(8) {
+                    let end = row.saturating_add(8).min(end);
+                    let words: Vec<String> = (row..end).map(|a| memory[a].to_string()).collect();
+                    writeln!(text, "{:04}: {}", row, words.join(" ")).unwrap();
+                }
+            }
+            Command::Set(address, value) => {
+                self.history.poke(&mut self.machine, address, value);
+            }
+            Command::Input(values) => {
+                values
+                    .into_iter()
+                    .for_each(|value| self.machine.push_input(value));
+            }
+            Command::List(address, count) => {
+                let mut address = address.unwrap_or_else(|| self.machine.pc());
+                for _ in 0..count {
+                    let line = self.line_at(address);
+                    let marker = if self.breakpoints.contains(&address) {
+                        '*'
+                    } else {
+                        ' '
+                    };
+                    writeln!(text, "{}{}", marker, line).unwrap();
+                    address += line.len();
+                }
+            }
+            Command::Save(path) => {
+                if let Err(error) = self.machine.save_to_path(&path) {
+                    writeln!(text, "{}: {}", path, error).unwrap();
+                }
+            }
+            Command::Load(path) => match Machine::load_from_path(&path) {
+                Ok(machine) => {
+                    self.machine = machine;
+                    self.history.clear();
+                    writeln!(text, "{}", self.location()).unwrap();
+                }
+                Err(error) => writeln!(text, "{}: {}", path, error).unwrap(),
+            },
+            Command::Info => {
+                writeln!(text, "{}", self.location()).unwrap();
+                let join = |set: &BTreeSet<usize>| {
+                    set.iter()
+                        .map(|a| a.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                };
+                writeln!(text, "breakpoints: {}", join(&self.breakpoints)).unwrap();
+                writeln!(text, "watchpoints: {}", join(&self.watchpoints)).unwrap();
+            }
+            Command::Help => text.push_str(HELP),
+            Command::Quit => {}
+        }
+        text
+    }
+
+    // Steps until `limit` instructions have run or something stops the
+    // machine. A breakpoint at the starting pc does not stop it again.
+    fn advance(&mut self, limit: Option<usize>) -> String {
+        let mut text = String::new();
+        let mut steps = 0;
+        while limit != Some(steps) {
+            let mut watch = Watch {
+                addresses: &self.watchpoints,
+                hit: None,
+            };
+            match self.history.step_with(&mut self.machine, &mut watch) {
+                Err(error) => {
+                    writeln!(text, "fault: {}", error).unwrap();
+                    break;
+                }
+                Ok(Some(Status::Halted)) => {
+                    writeln!(text, "halted").unwrap();
+                    break;
+                }
+                Ok(Some(Status::NeedsInput)) => {
+                    writeln!(text, "waiting for input").unwrap();
+                    break;
+                }
+                Ok(Some(Status::Output(value))) => writeln!(text, "output {}", value).unwrap(),
+                Ok(None) => {}
+            }
+            steps += 1;
+            if let Some((address, old, new)) = watch.hit {
+                writeln!(text, "watchpoint [{}]: {} -> {}", address, old, new).unwrap();
+                break;
+            }
+            if limit != Some(steps) && self.breakpoints.contains(&self.machine.pc()) {
+                writeln!(text, "breakpoint at {:04}", self.machine.pc()).unwrap();
+                break;
+            }
+        }
+        writeln!(text, "{}", self.location()).unwrap();
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debugger::{Command, Debugger};
+    use crate::{Machine, Program};
+
+    fn debugger(program: Vec<i64>) -> Debugger {
+        Debugger::new(Machine::new(program))
+    }
+
+    fn run(debugger: &mut Debugger, line: &str) -> String {
+        debugger.execute(line.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!("s".parse(), Ok(Command::Step(1)));
+        assert_eq!("step 5".parse(), Ok(Command::Step(5)));
+        assert_eq!("x 100".parse(), Ok(Command::Examine(100, 8)));
+        assert_eq!("set 3 -7".parse(), Ok(Command::Set(3, -7)));
+        assert_eq!("input 1 2".parse(), Ok(Command::Input(vec![1, 2])));
+        assert_eq!(
+            "input 1 x".parse::<Command>(),
+            Err("invalid number 'x'".to_string())
+        );
+        assert!(Command::Step(1).repeats() && !Command::Set(3, -7).repeats());
+        assert_eq!("list".parse(), Ok(Command::List(None, 10)));
+        assert_eq!(
+            "b".parse::<Command>(),
+            Err("b needs an address".to_string())
+        );
+        assert_eq!(
+            "x -1".parse::<Command>(),
+            Err("invalid address '-1'".to_string())
+        );
+        assert_eq!(
+            "frobnicate".parse::<Command>(),
+            Err("unknown command 'frobnicate'".to_string())
+        );
+    }
+
+    #[test]
+    fn steps_and_shows_location() {
+        let mut debugger = debugger(vec![109, 5, 1101, 2, 3, 0, 99]);
+        assert_eq!(
+            run(&mut debugger, "step"),
+            "0002: ADD #2, #3 -> [0]  (rb 5)\n"
+        );
+        assert_eq!(
+            run(&mut debugger, "step 0"),
+            "0002: ADD #2, #3 -> [0]  (rb 5)\n"
+        );
+        assert_eq!(run(&mut debugger, "step 5"), "halted\n0006: HLT  (rb 5)\n");
+        assert_eq!(debugger.machine().memory()[0], 5);
+    }
+
+    #[test]
+    fn stops_at_breakpoints_and_watchpoints() {
+        // counts [20] down from 3, writing [21] each time around
+        let program = vec![
+            1001, 20, -1, 20, 1001, 21, 1, 21, 1005, 20, 0, 99, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0,
+        ];
+        let mut debugger = debugger(program);
+        run(&mut debugger, "break 8");
+        assert_eq!(
+            run(&mut debugger, "c"),
+            "breakpoint at 0008\n0008: JNZ [20], #0  (rb 0)\n"
+        );
+        run(&mut debugger, "delete 8");
+        run(&mut debugger, "watch 21");
+        assert_eq!(
+            run(&mut debugger, "c"),
+            "watchpoint [21]: 1 -> 2\n0008: JNZ [20], #0  (rb 0)\n"
+        );
+        run(&mut debugger, "unwatch 21");
+        assert_eq!(run(&mut debugger, "c"), "halted\n0011: HLT  (rb 0)\n");
+        assert_eq!(run(&mut debugger, "x 20 2"), "0020: 0 3\n");
+    }
+
+    #[test]
+    fn edits_memory_and_feeds_input() {
+        let mut debugger = debugger(vec![3, 0, 4, 0, 99]);
+        assert_eq!(
+            run(&mut debugger, "c"),
+            "waiting for input\n0000: INP -> [0]  (rb 0)\n"
+        );
+        run(&mut debugger, "input 42");
+        run(&mut debugger, "set 1 5");
+        run(&mut debugger, "set 3 5");
+        assert_eq!(
+            run(&mut debugger, "c"),
+            "output 42\nhalted\n0004: HLT  (rb 0)\n"
+        );
+        assert_eq!(run(&mut debugger, "x 0 6"), "0000: 3 5 4 5 99 42\n");
+
+        assert_eq!(run(&mut debugger, "x 0 100000").lines().count(), 128);
+        let far = run(&mut debugger, "x 9223372036854775807 9223372036854775807");
+        assert_eq!(far.lines().count(), 128);
+        assert!(far.starts_with("9223372036854775807: 0 0 0 0 0 0 0 0\n"));
+    }
+
+    #[test]
+    fn lists_and_reports_faults() {
+        let mut debugger = debugger(vec![1101, 1, 2, 3, 42]);
+        run(&mut debugger, "b 4");
+        assert_eq!(
+            run(&mut debugger, "list 0 2"),
+            " 0000: ADD #1, #2 -> [3]\n*0004: DATA 42\n"
+        );
+        assert_eq!(
+            run(&mut debugger, "info"),
+            "0000: ADD #1, #2 -> [3]  (rb 0)\nbreakpoints: 4\nwatchpoints: \n"
+        );
+        run(&mut debugger, "c");
+        assert_eq!(
+            run(&mut debugger, "c"),
+            "fault: 0004: unknown opcode 42\n0004: DATA 42  (rb 0)\n"
+        );
+    }
+
+    #[test]
+    fn saves_and_loads_snapshots() {
+        let name = format!("intcode-dbg-test-{}.txt", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_str().unwrap();
+        let mut debugger = debugger(vec![1001, 5, 1, 5, 99, 0]);
+        run(&mut debugger, &format!("save {}", path));
+        run(&mut debugger, "c");
+        assert_eq!(run(&mut debugger, "x 5 1"), "0005: 1\n");
+        assert_eq!(
+            run(&mut debugger, &format!("load {}", path)),
+            "0000: ADD [5], #1 -> [5]  (rb 0)\n"
+        );
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(run(&mut debugger, "x 5 1"), "0005: 0\n");
+        assert!(run(&mut debugger, "load no/such/file").starts_with("no/such/file: "));
+    }
+
+    #[test]
+    fn steps_backwards() {
+        // outputs 0, 1, 2 while counting [14] up
+        let mut debugger = debugger(vec![
+            4, 14, 1001, 14, 1, 14, 1007, 14, 3, 15, 1005, 15, 0, 99, 0, 0,
+        ]);
+        assert_eq!(
+            run(&mut debugger, "c"),
+            "output 0\noutput 1\noutput 2\nhalted\n0013: HLT  (rb 0)\n"
+        );
+        assert_eq!(run(&mut debugger, "back"), "0010: JNZ [15], #0  (rb 0)\n");
+        assert_eq!(
+            run(&mut debugger, "back-output 1"),
+            "0000: OUT [14]  (rb 0)\n"
+        );
+        assert_eq!(debugger.machine().output(), &[0]);
+        assert_eq!(
+            run(&mut debugger, "back-write 14"),
+            "0002: ADD [14], #1 -> [14]  (rb 0)\n"
+        );
+        run(&mut debugger, "set 14 5");
+        assert_eq!(run(&mut debugger, "x 14 1"), "0014: 5\n");
+        run(&mut debugger, "back");
+        assert_eq!(run(&mut debugger, "x 14 1"), "0014: 0\n");
+        assert_eq!(
+            run(&mut debugger, "back 5"),
+            "start of history\n0000: OUT [14]  (rb 0)\n"
+        );
+        assert_eq!(
+            run(&mut debugger, "back-write 3"),
+            "no write to [3] in history\n0000: OUT [14]  (rb 0)\n"
+        );
+    }
+
+    #[test]
+    fn day9_self_test() {
+        let program = Program::from_path("../aoc9/input.txt").unwrap();
+        let mut debugger = Debugger::new(Machine::with_input(program, &[1]));
+        let text = run(&mut debugger, "continue");
+        assert!(text.starts_with("output 3345854957\nhalted\n"), "{}", text);
+    }
+}
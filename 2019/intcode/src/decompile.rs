@@ -0,0 +1,984 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+use crate::calls::{adjustment, local, moved_after, moved_by_block, slots, CallGraph, Function};
+use crate::disasm::Line;
+use crate::flow::{Block, EdgeKind, Exit, Graph};
+use crate::instruction::Instruction;
+use crate::{ADD, ADJ, EQU, HLT, IMMEDIATE, INP, JNZ, JZ, LES, MUL, OUT, POSITION, RELATIVE};
+
+// Scratch addresses accessed at least this often become named temporaries.
+const TEMPORARY_USES: usize = 4;
+
+/// Decompiles the code reachable from address 0 into pseudo-Rust, one `fn`
+/// per function found by `CallGraph`.
+///
+/// Addresses outside the code that are used often are named `t<address>`;
+/// other memory is `mem[<address>]`. Inside a function, slots of its frame
+/// are `arg<n>` or `local<n>`, and those of the frames it calls `out<n>`.
+/// Flags computed by `LES` or `EQU` just before a jump tests them are folded
+/// into the condition. What does not fit `if`, `loop` and `while` becomes a
+/// `goto` to a labelled block.
+pub fn decompile(words: &[i64]) -> String {
+    decompile_from(words, &[0])
+}
+
+/// Decompiles the code reachable from `entries`, each of which is entered
+/// with the relative base at 0. Code that is only reached after modifying
+/// itself, like that of the day 5 diagnostics, needs the address it ends
+/// up jumping to.
+pub fn decompile_from(words: &[i64], entries: &[usize]) -> String {
+    let graph = Graph::with_entries(words, entries);
+    let calls = CallGraph::new(&graph);
+    let temporaries = temporaries(&graph);
+
+    let mut text = String::new();
+    for (address, uses) in temporaries.iter() {
+        writeln!(text, "// t{}: [{}], {} uses", address, address, uses).unwrap();
+    }
+    for function in calls.functions() {
+        if !text.is_empty() {
+            writeln!(text).unwrap();
+        }
+        let mut decompiler = Decompiler::new(&graph, &calls, function, &temporaries);
+        let body = decompiler.body();
+        decompiler.signature(&mut text);
+        decompiler.render(&body, 1, &mut text);
+        writeln!(text, "}}").unwrap();
+    }
+    text
+}
+
+/// Addresses outside the code that the code writes and uses at least
+/// `TEMPORARY_USES` times, with how often.
+pub fn temporaries(graph: &Graph) -> BTreeMap<usize, usize> {
+    let mut code = BTreeSet::new();
+    let mut uses: HashMap<usize, (usize, bool)> = HashMap::new();
+    for line in graph.blocks().flat_map(|block| block.lines.iter()) {
+        code.extend(line.address..line.address + line.len());
+        let instruction = match line.instruction {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+        for index in 0..instruction.parameters() {
+            let word = line.words[index + 1];
+            if instruction.modes[index] != POSITION || word < 0 {
+                continue;
+            }
+            let entry = uses.entry(word as usize).or_default();
+            entry.0 += 1;
+            entry.1 |= instruction.destination() == Some(index);
+        }
+    }
+    uses.into_iter()
+        .filter(|(address, (count, written))| {
+            *written && *count >= TEMPORARY_USES && !code.contains(address)
+        })
+        .map(|(address, (count, _))| (address, count))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
+    left: String,
+    operator: &'static str,
+    right: String,
+}
+
+impl Condition {
+    fn negate(self) -> Condition {
+        let operator = match self.operator {
+            "<" => ">=",
+            ">=" => "<",
+            "==" => "!=",
+            _ => "==",
+        };
+        Condition { operator, ..self }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Statement {
+    Code(String),
+    /// Code after which execution does not go on, like `halt();`.
+    End(String),
+    /// The start of a block, shown if something jumps to it with `goto`.
+    Label(usize),
+    If(Condition, Vec<Statement>, Vec<Statement>),
+    Loop(Vec<Statement>),
+    While(Condition, Vec<Statement>),
+    Break,
+    Continue,
+    Goto(usize),
+}
+
+fn is_empty(statements: &[Statement]) -> bool {
+    statements
+        .iter()
+        .all(|statement| matches!(statement, Statement::Label(_)))
+}
+
+fn terminal(statements: &[Statement]) -> bool {
+    match statements
+        .iter()
+        .rev()
+        .find(|statement| !matches!(statement, Statement::Label(_)))
+    {
+        Some(Statement::End(_)) | Some(Statement::Break) | Some(Statement::Continue) => true,
+        Some(Statement::Goto(_)) => true,
+        Some(Statement::If(_, then, otherwise)) => terminal(then) && terminal(otherwise),
+        _ => false,
+    }
+}
+
+// Removes a `continue` that the end of a loop body makes redundant.
+fn strip_continue(statements: &mut Vec<Statement>) {
+    match statements.last_mut() {
+        Some(Statement::Continue) => {
+            statements.pop();
+        }
+        Some(Statement::If(_, then, otherwise)) => {
+            strip_continue(then);
+            strip_continue(otherwise);
+        }
+        _ => {}
+    }
+}
+
+fn push_if(
+    out: &mut Vec<Statement>,
+    condition: Condition,
+    then: Vec<Statement>,
+    otherwise: Vec<Statement>,
+) {
+    let (condition, then, otherwise) = if is_empty(&then) {
+        (condition.negate(), otherwise, then)
+    } else {
+        (condition, then, otherwise)
+    };
+    if is_empty(&then) {
+        out.extend(then);
+        out.extend(otherwise);
+        return;
+    }
+    if terminal(&then) || is_empty(&otherwise) {
+        out.push(Statement::If(condition, then, Vec::new()));
+        out.extend(otherwise);
+    } else {
+        out.push(Statement::If(condition, then, otherwise));
+    }
+}
+
+// `loop { if c { break; } ... }` is `while !c { ... }`.
+fn push_loop(out: &mut Vec<Statement>, mut body: Vec<Statement>) {
+    strip_continue(&mut body);
+    let labels = body
+        .iter()
+        .take_while(|statement| matches!(statement, Statement::Label(_)))
+        .count();
+    if let Some(Statement::If(condition, then, otherwise)) = body.get(labels) {
+        if then == &[Statement::Break] && otherwise.is_empty() {
+            let condition = condition.clone().negate();
+            let rest = body.split_off(labels + 1);
+            body.pop();
+            out.extend(body);
+            out.push(Statement::While(condition, rest));
+            return;
+        }
+    }
+    out.push(Statement::Loop(body));
+}
+
+struct NaturalLoop {
+    body: BTreeSet<usize>,
+    follow: Option<usize>,
+}
+
+struct Decompiler<'a> {
+    graph: &'a Graph,
+    calls: &'a CallGraph,
+    function: &'a Function,
+    temporaries: &'a BTreeMap<usize, usize>,
+    // whether the relative base starts at 0, as at the entry of the program
+    absolute: bool,
+    moved: BTreeMap<usize, Option<i64>>,
+    successors: BTreeMap<usize, Vec<usize>>,
+    postdominator: BTreeMap<usize, usize>,
+    loops: BTreeMap<usize, NaturalLoop>,
+    // headers of the loops being decompiled, innermost last
+    active: Vec<usize>,
+    emitted: BTreeSet<usize>,
+    gotos: BTreeSet<usize>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn new(
+        graph: &'a Graph,
+        calls: &'a CallGraph,
+        function: &'a Function,
+        temporaries: &'a BTreeMap<usize, usize>,
+    ) -> Decompiler<'a> {
+        let moved = moved_by_block(graph, function.entry);
+        let successors: BTreeMap<usize, Vec<usize>> = moved
+            .keys()
+            .map(|&start| {
+                let targets = graph.block(start).map_or(Vec::new(), |block| {
+                    block
+                        .successors
+                        .iter()
+                        .filter(|edge| local(edge) && moved.contains_key(&edge.target))
+                        .map(|edge| edge.target)
+                        .collect()
+                });
+                (start, targets)
+            })
+            .collect();
+        let mut decompiler = Decompiler {
+            graph,
+            calls,
+            function,
+            temporaries,
+            absolute: graph.entries().contains(&function.entry),
+            moved,
+            successors,
+            postdominator: BTreeMap::new(),
+            loops: BTreeMap::new(),
+            active: Vec::new(),
+            emitted: BTreeSet::new(),
+            gotos: BTreeSet::new(),
+        };
+        decompiler.find_postdominators();
+        decompiler.find_loops();
+        decompiler
+    }
+
+    fn find_postdominators(&mut self) {
+        // `None` stands for every block, for those that never reach an exit
+        let mut sets: BTreeMap<usize, Option<BTreeSet<usize>>> = BTreeMap::new();
+        for (&start, targets) in self.successors.iter() {
+            let set = if targets.is_empty() {
+                Some(std::iter::once(start).collect())
+            } else {
+                None
+            };
+            sets.insert(start, set);
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (&start, targets) in self.successors.iter().rev() {
+                if targets.is_empty() {
+                    continue;
+                }
+                let mut set: Option<BTreeSet<usize>> = None;
+                for target in targets.iter() {
+                    set = match (set, &sets[target]) {
+                        (None, other) => other.clone(),
+                        (set, None) => set,
+                        (Some(set), Some(other)) => {
+                            Some(set.intersection(other).copied().collect())
+                        }
+                    };
+                }
+                if let Some(set) = set.as_mut() {
+                    set.insert(start);
+                }
+                if sets[&start] != set {
+                    sets.insert(start, set);
+                    changed = true;
+                }
+            }
+        }
+        for (&start, set) in sets.iter() {
+            let set = match set {
+                Some(set) => set,
+                None => continue,
+            };
+            // the postdominators form a chain: the nearest has all the others
+            let nearest = set.iter().copied().filter(|&other| other != start).find(
+                |other| matches!(&sets[other], Some(other_set) if other_set.len() + 1 == set.len()),
+            );
+            if let Some(nearest) = nearest {
+                self.postdominator.insert(start, nearest);
+            }
+        }
+    }
+
+    fn find_loops(&mut self) {
+        // back edges, found depth first from the entry
+        let mut back_edges = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut path = BTreeSet::new();
+        let mut stack = vec![(self.function.entry, 0)];
+        seen.insert(self.function.entry);
+        path.insert(self.function.entry);
+        while let Some((start, index)) = stack.pop() {
+            match self.successors[&start].get(index) {
+                Some(&target) => {
+                    stack.push((start, index + 1));
+                    if path.contains(&target) {
+                        back_edges.push((start, target));
+                    } else if seen.insert(target) {
+                        path.insert(target);
+                        stack.push((target, 0));
+                    }
+                }
+                None => {
+                    path.remove(&start);
+                }
+            }
+        }
+
+        let mut predecessors: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (&start, targets) in self.successors.iter() {
+            for &target in targets.iter() {
+                predecessors.entry(target).or_default().push(start);
+            }
+        }
+        for (latch, header) in back_edges {
+            let mut body: BTreeSet<usize> = std::iter::once(header).collect();
+            let mut pending = vec![latch];
+            while let Some(start) = pending.pop() {
+                if body.insert(start) {
+                    pending.extend(predecessors.get(&start).into_iter().flatten().copied());
+                }
+            }
+            let natural = self.loops.entry(header).or_insert_with(|| NaturalLoop {
+                body: BTreeSet::new(),
+                follow: None,
+            });
+            natural.body.extend(body);
+        }
+        let successors = &self.successors;
+        let postdominator = &self.postdominator;
+        for (&header, natural) in self.loops.iter_mut() {
+            let exits: BTreeSet<usize> = natural
+                .body
+                .iter()
+                .flat_map(|start| successors[start].iter().copied())
+                .filter(|target| !natural.body.contains(target))
+                .collect();
+            natural.follow = postdominator
+                .get(&header)
+                .copied()
+                .filter(|follow| exits.contains(follow))
+                .or_else(|| exits.iter().next().copied());
+        }
+    }
+
+    fn signature(&self, text: &mut String) {
+        if self.absolute {
+            match self.function.entry {
+                0 => writeln!(text, "fn main() {{").unwrap(),
+                entry => writeln!(text, "fn entry{}() {{", entry).unwrap(),
+            }
+            return;
+        }
+        let arguments: Vec<String> = self
+            .function
+            .arguments
+            .iter()
+            .map(|&slot| format!("{}: i64", self.slot(slot)))
+            .collect();
+        let results = match self.function.results.len() {
+            0 => String::new(),
+            1 => " -> i64".to_string(),
+            count => format!(" -> ({})", vec!["i64"; count].join(", ")),
+        };
+        writeln!(
+            text,
+            "fn f{}({}){} {{",
+            self.function.entry,
+            arguments.join(", "),
+            results
+        )
+        .unwrap();
+    }
+
+    fn memory(&self, address: i64) -> String {
+        if address >= 0 && self.temporaries.contains_key(&(address as usize)) {
+            format!("t{}", address)
+        } else {
+            format!("mem[{}]", address)
+        }
+    }
+
+    // A slot numbered from the relative base on entry.
+    fn slot(&self, slot: i64) -> String {
+        let frame = self.function.frame_size;
+        if self.absolute {
+            self.memory(slot)
+        } else if slot == 0 {
+            "ret".to_string()
+        } else if slot > 0 && slot < frame {
+            if self.function.arguments.contains(&slot) {
+                format!("arg{}", slot)
+            } else {
+                format!("local{}", slot)
+            }
+        } else if slot >= frame && frame > 0 {
+            format!("out{}", slot - frame)
+        } else {
+            format!("mem[rb{:+}]", slot)
+        }
+    }
+
+    fn relative(&self, moved: Option<i64>, offset: i64) -> String {
+        match moved {
+            Some(moved) => match moved.checked_add(offset) {
+                Some(slot) => self.slot(slot),
+                None => format!("mem[rb{:+}]", offset),
+            },
+            None => format!("mem[rb{:+}]", offset),
+        }
+    }
+
+    fn operand(
+        &self,
+        line: &Line,
+        instruction: &Instruction,
+        index: usize,
+        moved: Option<i64>,
+    ) -> String {
+        let word = line.words[index + 1];
+        match (instruction.modes[index], moved) {
+            (IMMEDIATE, _) => word.to_string(),
+            (RELATIVE, _) => self.relative(moved, word),
+            _ => self.memory(word),
+        }
+    }
+
+    fn expression(&self, line: &Line, instruction: &Instruction, moved: Option<i64>) -> String {
+        let a = self.operand(line, instruction, 0, moved);
+        let b = self.operand(line, instruction, 1, moved);
+        let immediate = |index: usize| {
+            Some(line.words[index + 1]).filter(|_| instruction.modes[index] == IMMEDIATE)
+        };
+        match instruction.opcode {
+            ADD if immediate(0) == Some(0) => b,
+            ADD if immediate(1) == Some(0) => a,
+            ADD => match immediate(1) {
+                Some(n) if n < 0 => format!("{} - {}", a, -(n as i128)),
+                _ => format!("{} + {}", a, b),
+            },
+            MUL if immediate(0) == Some(1) => b,
+            MUL if immediate(1) == Some(1) => a,
+            MUL if immediate(1) == Some(-1) => format!("-{}", a),
+            MUL => format!("{} * {}", a, b),
+            LES => format!("({} < {}) as i64", a, b),
+            EQU => format!("({} == {}) as i64", a, b),
+            _ => String::new(),
+        }
+    }
+
+    fn body(&mut self) -> Vec<Statement> {
+        let mut out = Vec::new();
+        self.walk(Some(self.function.entry), None, &mut out);
+        out
+    }
+
+    // Decompiles from `start` until reaching `stop` or the end of the
+    // enclosing loop body.
+    fn walk(&mut self, mut start: Option<usize>, stop: Option<usize>, out: &mut Vec<Statement>) {
+        while let Some(at) = start {
+            if Some(at) == stop {
+                return;
+            }
+            if let Some(&header) = self.active.last() {
+                if at == header {
+                    out.push(Statement::Continue);
+                    return;
+                }
+                if Some(at) == self.loops[&header].follow {
+                    out.push(Statement::Break);
+                    return;
+                }
+            }
+            if self.emitted.contains(&at) || !self.moved.contains_key(&at) {
+                self.gotos.insert(at);
+                out.push(Statement::Goto(at));
+                return;
+            }
+            if self.loops.contains_key(&at) {
+                self.active.push(at);
+                let mut body = Vec::new();
+                let next = self.block(at, &mut body);
+                self.walk(next, None, &mut body);
+                self.active.pop();
+                push_loop(out, body);
+                start = self.loops[&at].follow;
+            } else {
+                start = self.block(at, out);
+            }
+        }
+    }
+
+    // Decompiles one block and the branches it starts, returning where
+    // execution goes on.
+    fn block(&mut self, start: usize, out: &mut Vec<Statement>) -> Option<usize> {
+        self.emitted.insert(start);
+        out.push(Statement::Label(start));
+        let graph = self.graph;
+        let block = graph.block(start)?;
+        let lines = &block.lines;
+        let last = block.last();
+        let mut moved = self.moved[&start];
+        let at_last = moved_after(&lines[..lines.len() - 1], moved);
+        let at_end = moved_after(lines, moved);
+
+        let call = block
+            .successors
+            .iter()
+            .find(|edge| edge.kind == EdgeKind::Call)
+            .and_then(|_| self.calls.call_at(last.address));
+        let callee = call.and_then(|call| self.calls.function(call.callee));
+        let condition = self.condition(block, at_last);
+        // the flag folded into the condition
+        let folded = condition.as_ref().and_then(|(_, folded)| *folded);
+
+        // how far the base moves within the block, which is enough to find
+        // the slots of a call even where `moved` is not known
+        let mut within = Some(0);
+        let within_end = moved_after(lines, within);
+        let mut arguments: BTreeMap<i64, String> = BTreeMap::new();
+        for line in lines[..lines.len() - 1].iter() {
+            let instruction = line.instruction?;
+            if Some(line.address) != folded {
+                // relative to the base at the call
+                let slot = within
+                    .zip(within_end)
+                    .and_then(|(within, end)| within.checked_sub(end))
+                    .and_then(|moved| slots(line, &instruction, moved).1)
+                    .filter(|_| call.is_some());
+                match (slot, callee) {
+                    (Some(0), _) => {}
+                    // an argument read by `INP` stays a statement, as the
+                    // input has to be taken where it is
+                    (Some(slot), Some(callee))
+                        if callee.arguments.contains(&slot)
+                            && [ADD, MUL, LES, EQU].contains(&instruction.opcode) =>
+                    {
+                        arguments.insert(slot, self.expression(line, &instruction, moved));
+                    }
+                    _ => out.extend(self.statement(line, &instruction, moved)),
+                }
+            }
+            let adjusted = adjustment(line, &instruction);
+            moved = moved
+                .zip(adjusted)
+                .and_then(|(moved, n)| moved.checked_add(n));
+            within = within
+                .zip(adjusted)
+                .and_then(|(within, n)| within.checked_add(n));
+        }
+
+        if let (Some(call), Some(callee)) = (call, callee) {
+            let passed: Vec<String> = callee
+                .arguments
+                .iter()
+                .map(|slot| {
+                    arguments
+                        .remove(slot)
+                        .unwrap_or_else(|| self.relative(at_end, *slot))
+                })
+                .collect();
+            let results: Vec<String> = callee
+                .results
+                .iter()
+                .map(|&slot| self.relative(at_end, slot))
+                .collect();
+            let call_text = format!("f{}({})", call.callee, passed.join(", "));
+            out.push(Statement::Code(match results.len() {
+                0 => format!("{};", call_text),
+                1 => format!("{} = {};", results[0], call_text),
+                _ => format!("({}) = {};", results.join(", "), call_text),
+            }));
+            return Some(call.return_site);
+        }
+
+        let instruction = match last.instruction {
+            Some(instruction) => instruction,
+            None => {
+                out.push(Statement::End(format!("// data {}", last.words[0])));
+                return None;
+            }
+        };
+        match block.exit {
+            Exit::Halt => {
+                out.push(Statement::End("halt();".to_string()));
+                return None;
+            }
+            Exit::Invalid => return None,
+            Exit::Computed | Exit::OutOfRange => {
+                let end = if block.exit == Exit::OutOfRange {
+                    format!("goto {}; // out of range", last.words[2])
+                } else if self.function.returns.contains(&last.address) {
+                    let results: Vec<String> = self
+                        .function
+                        .results
+                        .iter()
+                        .map(|&slot| self.slot(slot))
+                        .collect();
+                    match results.len() {
+                        0 => "return;".to_string(),
+                        1 => format!("return {};", results[0]),
+                        _ => format!("return ({});", results.join(", ")),
+                    }
+                } else {
+                    format!("goto *{};", self.operand(last, &instruction, 1, at_last))
+                };
+                let not_taken = block.successors.first().map(|edge| edge.target);
+                return match condition {
+                    Some((condition, _)) if not_taken.is_some() => {
+                        out.push(Statement::If(
+                            condition,
+                            vec![Statement::End(end)],
+                            Vec::new(),
+                        ));
+                        not_taken
+                    }
+                    _ => {
+                        out.push(Statement::End(end));
+                        None
+                    }
+                };
+            }
+            Exit::Known => {}
+        }
+        if instruction.opcode != JNZ && instruction.opcode != JZ {
+            out.extend(self.statement(last, &instruction, at_last));
+        }
+
+        let taken = block
+            .successors
+            .iter()
+            .find(|edge| edge.kind == EdgeKind::Taken);
+        let not_taken = block
+            .successors
+            .iter()
+            .find(|edge| edge.kind == EdgeKind::NotTaken);
+        let (taken, not_taken, condition) = match (taken, not_taken, condition) {
+            (Some(taken), Some(not_taken), Some((condition, _))) => {
+                (taken.target, not_taken.target, condition)
+            }
+            _ => return block.successors.first().map(|edge| edge.target),
+        };
+        // branches that meet at the end of a loop body `continue` or
+        // `break` on their own
+        let join = self.postdominator.get(&start).copied().filter(|&join| {
+            self.active
+                .last()
+                .is_none_or(|header| join != *header && Some(join) != self.loops[header].follow)
+        });
+        let mut then = Vec::new();
+        self.walk(Some(taken), join, &mut then);
+        if join.is_some() {
+            let mut otherwise = Vec::new();
+            self.walk(Some(not_taken), join, &mut otherwise);
+            push_if(out, condition, then, otherwise);
+            return join;
+        }
+        if terminal(&then) {
+            push_if(out, condition, then, Vec::new());
+            return Some(not_taken);
+        }
+        let mut otherwise = Vec::new();
+        self.walk(Some(not_taken), None, &mut otherwise);
+        if terminal(&otherwise) {
+            push_if(out, condition.negate(), otherwise, Vec::new());
+            out.extend(then);
+        } else {
+            push_if(out, condition, then, otherwise);
+        }
+        None
+    }
+
+    // The condition under which the last instruction of a block jumps, and
+    // the address of a flag computation folded into it.
+    fn condition(&self, block: &Block, moved: Option<i64>) -> Option<(Condition, Option<usize>)> {
+        let last = block.last();
+        let instruction = last.instruction?;
+        if instruction.opcode != JNZ && instruction.opcode != JZ {
+            return None;
+        }
+        let flag = block
+            .lines
+            .len()
+            .checked_sub(2)
+            .map(|index| &block.lines[index]);
+        let folded = flag.and_then(|flag| {
+            let set = flag.instruction?;
+            let same = (set.opcode == LES || set.opcode == EQU)
+                && instruction.modes[0] != IMMEDIATE
+                && set.modes[2] == instruction.modes[0]
+                && flag.words[3] == last.words[1];
+            if !same {
+                return None;
+            }
+            let condition = Condition {
+                left: self.operand(flag, &set, 0, moved),
+                operator: if set.opcode == LES { "<" } else { "==" },
+                right: self.operand(flag, &set, 1, moved),
+            };
+            Some((condition, flag.address))
+        });
+        let (condition, folded) = match folded {
+            Some((condition, address)) => (condition, Some(address)),
+            None => (
+                Condition {
+                    left: self.operand(last, &instruction, 0, moved),
+                    operator: "!=",
+                    right: "0".to_string(),
+                },
+                None,
+            ),
+        };
+        let condition = if instruction.opcode == JZ {
+            condition.negate()
+        } else {
+            condition
+        };
+        Some((condition, folded))
+    }
+
+    fn statement(
+        &self,
+        line: &Line,
+        instruction: &Instruction,
+        moved: Option<i64>,
+    ) -> Option<Statement> {
+        let operand = |index| self.operand(line, instruction, index, moved);
+        let text = match instruction.opcode {
+            ADD | MUL | LES | EQU => {
+                let destination = operand(2);
+                let value = self.expression(line, instruction, moved);
+                if destination == value {
+                    return None;
+                }
+                format!("{} = {};", destination, value)
+            }
+            INP => format!("{} = input();", operand(0)),
+            OUT => format!("output({});", operand(0)),
+            // moves of the base by a known amount are in the slot names
+            ADJ if moved.is_some() && adjustment(line, instruction).is_some() => return None,
+            ADJ => format!("rb += {};", operand(0)),
+            // jumps that end a block are decompiled with it, the rest never jump
+            JNZ | JZ => return None,
+            HLT => return Some(Statement::End("halt();".to_string())),
+            _ => return None,
+        };
+        Some(Statement::Code(text))
+    }
+
+    fn render(&self, statements: &[Statement], depth: usize, text: &mut String) {
+        let indent = "    ".repeat(depth);
+        for statement in statements.iter() {
+            match statement {
+                Statement::Code(code) | Statement::End(code) => {
+                    writeln!(text, "{}{}", indent, code).unwrap()
+                }
+                Statement::Label(address) if self.gotos.contains(address) => {
+                    writeln!(text, "{}'b{}:", "    ".repeat(depth - 1), address).unwrap()
+                }
+                Statement::Label(_) => {}
+                Statement::If(condition, then, otherwise) => {
+                    writeln!(
+                        text,
+                        "{}if {} {} {} {{",
+                        indent, condition.left, condition.operator, condition.right
+                    )
+                    .unwrap();
+                    self.render(then, depth + 1, text);
+                    if !otherwise.is_empty() {
+                        writeln!(text, "{}}} else {{", indent).unwrap();
+                        self.render(otherwise, depth + 1, text);
+                    }
+                    writeln!(text, "{}}}", indent).unwrap();
+                }
+                Statement::Loop(body) => {
+                    writeln!(text, "{}loop {{", indent).unwrap();
+                    self.render(body, depth + 1, text);
+                    writeln!(text, "{}}}", indent).unwrap();
+                }
+                Statement::While(condition, body) => {
+                    writeln!(
+                        text,
+                        "{}while {} {} {} {{",
+                        indent, condition.left, condition.operator, condition.right
+                    )
+                    .unwrap();
+                    self.render(body, depth + 1, text);
+                    writeln!(text, "{}}}", indent).unwrap();
+                }
+                Statement::Break => writeln!(text, "{}break;", indent).unwrap(),
+                Statement::Continue => writeln!(text, "{}continue;", indent).unwrap(),
+                Statement::Goto(address) => {
+                    writeln!(text, "{}goto 'b{};", indent, address).unwrap()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asm::assemble;
+    use crate::decompile::{decompile, decompile_from};
+    use crate::Program;
+
+    #[test]
+    fn recovers_loops_and_branches() {
+        let source = "
+                INP -> [n]
+            loop:
+                EQU [n], #0 -> [t]
+                JNZ [t], #done
+                ADD [sum], [n] -> [sum]
+                LES [sum], #100 -> [t]
+                JZ [t], #big
+                OUT [sum]
+            big:
+                INP -> [n]
+                JNZ #1, #loop
+            done:
+                OUT [sum]
+                HLT
+            n: db 0
+            sum: db 0
+            t: db 0
+        ";
+        let program: Vec<i64> = assemble(source).unwrap().into();
+        assert_eq!(
+            decompile(&program),
+            "\
+// t30: [30], 4 uses
+// t31: [31], 5 uses
+// t32: [32], 4 uses
+
+fn main() {
+    t30 = input();
+    while t30 != 0 {
+        t31 = t31 + t30;
+        if t31 < 100 {
+            output(t31);
+        }
+        t30 = input();
+    }
+    output(t31);
+    halt();
+}
+"
+        );
+    }
+
+    #[test]
+    fn argument_read_from_input() {
+        let source = "
+                ADJ #100
+                INP -> [rb+1]
+                ADD #ret, #0 -> [rb+0]
+                JNZ #1, #f
+            ret:
+                OUT [rb+1]
+                HLT
+            f:
+                ADJ #3
+                ADD [rb-2], #1 -> [rb-2]
+                ADJ #-3
+                JZ #0, [rb+0]
+        ";
+        let program: Vec<i64> = assemble(source).unwrap().into();
+        assert_eq!(
+            decompile_from(&program, &[0]),
+            "\
+fn main() {
+    mem[101] = input();
+    mem[101] = f14(mem[101]);
+    output(mem[101]);
+    halt();
+}
+
+fn f14(arg1: i64) -> i64 {
+    arg1 = arg1 + 1;
+    return arg1;
+}
+"
+        );
+    }
+
+    #[test]
+    fn slots_past_the_range_of_i64() {
+        let min = i64::MIN;
+        let program = [
+            109, -44007, 1, 1206, 2, 208, 20207, min, 2, 20105, 1, -2, 5, min, 19, 16, min, 4,
+        ];
+        let text = decompile(&program);
+        // -44007 + i64::MIN is not a slot of the frame
+        assert!(
+            text.contains("(mem[rb-9223372036854775808] < mem[2])"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn jumps_out_of_the_program() {
+        let text = decompile(&[1005, 6, 99999, 1106, 0, -1, 0]);
+        assert!(text.contains(
+            "
+    if mem[6] != 0 {
+        goto 99999; // out of range
+    }
+    goto -1; // out of range
+"
+        ));
+    }
+
+    #[test]
+    fn day9_function() {
+        let program: Vec<i64> = Program::from_path("../aoc9/input.txt").unwrap().into();
+        let text = decompile(&program);
+        assert!(text.contains(
+            "
+    if t1000 == 2 {
+        mem[rb+1] = f922(27);
+        mem[rb+1] = mem[rb+1] + 47633;
+        output(mem[rb+1]);
+        halt();
+    }
+"
+        ));
+        assert!(text.ends_with(
+            "
+fn f922(arg1: i64) -> i64 {
+    if arg1 >= 3 {
+        out1 = f922(arg1 - 1);
+        local2 = out1;
+        out1 = f922(arg1 - 3);
+        arg1 = out1 + local2;
+    }
+    return arg1;
+}
+"
+        ));
+    }
+
+    #[test]
+    fn day5_temporaries() {
+        let program: Vec<i64> = Program::from_path("../aoc5/input.txt").unwrap().into();
+        // with input 1 the first instruction turns [6] into `ADD` and goes
+        // on at 10
+        let text = decompile_from(&program, &[0, 10]);
+        assert!(text.starts_with("// t223: [223], 33 uses\n// t224: [224], 56 uses\n"));
+        assert!(text.contains(
+            "
+fn entry10() {
+    output(0);
+    t224 = mem[148] * 28;
+    t224 = t224 - 672;
+    output(t224);
+    t223 = t223 * 8;
+"
+        ));
+    }
+}
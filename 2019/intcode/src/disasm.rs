@@ -0,0 +1,192 @@
+use std::fmt;
+
+use crate::instruction::Instruction;
+use crate::memory::Memory;
+use crate::{IMMEDIATE, POSITION, RELATIVE};
+
+/// One line of a listing: a decoded instruction, or a single `DATA` word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub address: usize,
+    pub instruction: Option<Instruction>,
+    pub words: Vec<i64>,
+}
+
+impl Line {
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn text(&self) -> String {
+        let instruction = match self.instruction {
+            Some(instruction) => instruction,
+            None => return format!("DATA {}", self.words[0]),
+        };
+        let mut text = instruction.mnemonic().unwrap_or_default().to_string();
+        let mut sources = Vec::new();
+        let mut destination = None;
+        for (index, &mode) in instruction.modes[..instruction.parameters()]
+            .iter()
+            .enumerate()
+        {
+            let parameter = parameter(mode, self.words[index + 1]);
+            if instruction.destination() == Some(index) {
+                destination = Some(parameter);
+            } else {
+                sources.push(parameter);
+            }
+        }
+        if !sources.is_empty() {
+            text.push(' ');
+            text.push_str(&sources.join(", "));
+        }
+        if let Some(destination) = destination {
+            text.push_str(" -> ");
+            text.push_str(&destination);
+        }
+        text
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}: {}", self.address, self.text())
+    }
+}
+
+/// Formats a parameter as `[address]`, `#value` or `[rb+offset]`.
+pub fn parameter(mode: i64, word: i64) -> String {
+    match mode {
+        POSITION => format!("[{}]", word),
+        IMMEDIATE => format!("#{}", word),
+        RELATIVE if word < 0 => format!("[rb{}]", word),
+        RELATIVE => format!("[rb+{}]", word),
+        _ => format!("?{}", word),
+    }
+}
+
+/// Decodes the instruction at `address`, falling back to a `DATA` line when
+/// the word is not a well-formed instruction or its parameters run past the
+/// end of `words`.
+pub fn decode_at(words: &[i64], address: usize) -> Line {
+    let word = words.get(address).copied().unwrap_or(0);
+    let instruction = Instruction::parse(word).filter(|i| address + i.size() <= words.len());
+    let len = instruction.map_or(1, |i| i.size());
+    Line {
+        address,
+        instruction,
+        words: match words.get(address..address + len) {
+            Some(words) => words.to_vec(),
+            None => vec![word],
+        },
+    }
+}
+
+/// Decodes the instruction at `address` in a running machine's memory.
+pub fn decode_memory(memory: &Memory, address: usize) -> Line {
+    let words: Vec<i64> = (address..address + 4).map(|a| memory[a]).collect();
+    let mut line = decode_at(&words, 0);
+    line.address = address;
+    line
+}
+
+/// Decodes a whole program front to back.
+pub fn disassemble(words: &[i64]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut address = 0;
+    while address < words.len() {
+        let line = decode_at(words, address);
+        address += line.len();
+        lines.push(line);
+    }
+    lines
+}
+
+pub fn listing(words: &[i64]) -> String {
+    disassemble(words)
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disasm::{decode_at, disassemble, listing};
+    use crate::Program;
+
+    #[test]
+    fn lists_modes() {
+        let words = vec![
+            1001, 224, -672, 224, 21201, -2, -1, 1, 2106, 0, 0, 109, 3, 99,
+        ];
+        assert_eq!(
+            listing(&words),
+            "0000: ADD [224], #-672 -> [224]\n\
+             0004: ADD [rb-2], #-1 -> [rb+1]\n\
+             0008: JZ #0, [rb+0]\n\
+             0011: ADJ #3\n\
+             0013: HLT\n"
+        );
+    }
+
+    #[test]
+    fn lists_io() {
+        let words = vec![3, 225, 104, 0, 204, -1];
+        assert_eq!(
+            listing(&words),
+            "0000: INP -> [225]\n0002: OUT #0\n0004: OUT [rb-1]\n"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_data() {
+        let words = vec![99999, 11101, 0, 1, 2, 10099, -1, 1, 2];
+        let text: Vec<String> = disassemble(&words).iter().map(|l| l.text()).collect();
+        assert_eq!(
+            text,
+            vec![
+                "DATA 99999",
+                "DATA 11101",
+                "DATA 0",
+                "ADD [2], [10099] -> [-1]",
+                "DATA 1",
+                "DATA 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_single_instruction() {
+        let words = vec![1105, 1, 238];
+        let line = decode_at(&words, 0);
+        assert_eq!(line.to_string(), "0000: JNZ #1, #238");
+        assert_eq!(line.len(), 3);
+        assert_eq!(decode_at(&words, 7).to_string(), "0007: DATA 0");
+    }
+
+    #[test]
+    fn day5_listing() {
+        let program = Program::from_path("../aoc5/input.txt").unwrap();
+        let lines = disassemble(program.words());
+        assert_eq!(
+            lines.iter().map(|l| l.len()).sum::<usize>(),
+            program.words().len()
+        );
+        let text: Vec<String> = lines.iter().take(5).map(|l| l.to_string()).collect();
+        assert_eq!(
+            text,
+            vec![
+                "0000: INP -> [225]",
+                "0002: ADD [225], [6] -> [6]",
+                // the program patches 1100 into 1101 before running it
+                "0006: DATA 1100",
+                "0007: ADD [238], [225] -> [104]",
+                "0011: DATA 0",
+            ]
+        );
+    }
+}
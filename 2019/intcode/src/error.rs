@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::cell::Cell;
+use crate::memory::Memory;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnknownOpcode,
+    UnknownMode(i64),
+    NegativeAddress(i64),
+    AddressOverflow,
+    NegativeJump(i64),
+    ImmediateWrite,
+    InputExhausted,
+    /// An `ADD` or `MUL` result does not fit in the cell type.
+    Overflow,
+}
+
+/// A fault raised by a running machine. The machine stays at the faulting
+/// instruction; `memory` is a snapshot taken when the fault happened.
+#[derive(Clone)]
+pub struct IntcodeError<C = i64> {
+    pub kind: ErrorKind,
+    pub pc: usize,
+    pub opcode: C,
+    pub memory: Arc<Memory<C>>,
+}
+
+impl<C: Cell> fmt::Display for IntcodeError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}: ", self.pc)?;
+        match self.kind {
+            ErrorKind::UnknownOpcode => write!(f, "unknown opcode {}", self.opcode),
+            ErrorKind::UnknownMode(mode) => {
+                write!(f, "unknown mode {} in instruction {}", mode, self.opcode)
+            }
+            ErrorKind::NegativeAddress(address) => {
+                write!(
+                    f,
+                    "negative address {} in instruction {}",
+                    address, self.opcode
+                )
+            }
+            ErrorKind::AddressOverflow => {
+                write!(f, "address overflow in instruction {}", self.opcode)
+            }
+            ErrorKind::NegativeJump(target) => {
+                write!(
+                    f,
+                    "jump to negative address {} in instruction {}",
+                    target, self.opcode
+                )
+            }
+            ErrorKind::ImmediateWrite => {
+                write!(f, "write in immediate mode in instruction {}", self.opcode)
+            }
+            ErrorKind::InputExhausted => write!(f, "input exhausted"),
+            ErrorKind::Overflow => {
+                write!(f, "arithmetic overflow in instruction {}", self.opcode)
+            }
+        }
+    }
+}
+
+// The memory snapshot can be large, so only its size is shown.
+impl<C: Cell> fmt::Debug for IntcodeError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IntcodeError")
+            .field("kind", &self.kind)
+            .field("pc", &self.pc)
+            .field("opcode", &self.opcode)
+            .field("memory", &format_args!("<{} words>", self.memory.len()))
+            .finish()
+    }
+}
+
+impl<C: Cell> Error for IntcodeError<C> {}
@@ -0,0 +1,475 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::disasm::{decode_at, Line};
+use crate::instruction::Instruction;
+use crate::{ADD, HLT, IMMEDIATE, JNZ, JZ, MUL};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// On to the next instruction, which starts another block.
+    Next,
+    /// A conditional jump taken.
+    Taken,
+    /// A conditional jump not taken.
+    NotTaken,
+    /// An unconditional jump.
+    Jump,
+    /// A jump just after storing the address following it, i.e. a call.
+    Call,
+    /// From a call to the address it stored, where the callee returns.
+    AfterCall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub target: usize,
+    pub kind: EdgeKind,
+}
+
+/// How a block ends, besides its edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// All successors are known.
+    Known,
+    Halt,
+    /// A jump whose target is read from memory, like the relative-mode
+    /// return `JZ #0, [rb+0]`.
+    Computed,
+    /// A jump to an immediate address outside the program, which faults
+    /// if taken.
+    OutOfRange,
+    /// The block is a word that does not decode as an instruction.
+    Invalid,
+}
+
+/// Instructions executed one after the other: only the first is jumped to
+/// and only the last jumps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: usize,
+    pub lines: Vec<Line>,
+    pub exit: Exit,
+    pub successors: Vec<Edge>,
+}
+
+impl Block {
+    /// One past the last word of the block.
+    pub fn end(&self) -> usize {
+        self.lines
+            .last()
+            .map_or(self.start, |line| line.address + line.len())
+    }
+
+    pub fn last(&self) -> &Line {
+        self.lines.last().expect("blocks are never empty")
+    }
+}
+
+/// The control-flow graph of the code reachable from the entry points,
+/// found without running the program. Jumps are only followed when their
+/// target is immediate; code reached otherwise, e.g. through a computed
+/// jump, is missing unless given as an entry point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    entries: Vec<usize>,
+    blocks: BTreeMap<usize, Block>,
+}
+
+// What one instruction does to the flow, before blocks are formed.
+struct Step {
+    line: Line,
+    exit: Exit,
+    successors: Vec<Edge>,
+    // false when the next instruction simply follows
+    ends_block: bool,
+}
+
+fn immediate(line: &Line, instruction: &Instruction, index: usize) -> Option<i64> {
+    if instruction.modes[index] == IMMEDIATE {
+        Some(line.words[index + 1])
+    } else {
+        None
+    }
+}
+
+// The value stored by `ADD #k, #0` or `MUL #k, #1` and the like.
+fn stored_constant(words: &[i64], address: usize) -> Option<i64> {
+    let line = decode_at(words, address);
+    let instruction = line.instruction?;
+    let a = immediate(&line, &instruction, 0)?;
+    let b = immediate(&line, &instruction, 1)?;
+    match instruction.opcode {
+        ADD if a == 0 => Some(b),
+        ADD if b == 0 => Some(a),
+        MUL if a == 1 => Some(b),
+        MUL if b == 1 => Some(a),
+        _ => None,
+    }
+}
+
+fn step(words: &[i64], address: usize) -> Step {
+    let line = decode_at(words, address);
+    let next = address + line.len();
+    let follow = |kind| {
+        Some(next)
+            .filter(|&next| next < words.len())
+            .map(|target| Edge { target, kind })
+    };
+    let instruction = match line.instruction {
+        Some(instruction) => instruction,
+        None => {
+            return Step {
+                line,
+                exit: Exit::Invalid,
+                successors: Vec::new(),
+                ends_block: true,
+            }
+        }
+    };
+    let (condition, target) = match instruction.opcode {
+        HLT => {
+            return Step {
+                line,
+                exit: Exit::Halt,
+                successors: Vec::new(),
+                ends_block: true,
+            }
+        }
+        JNZ | JZ => (
+            immediate(&line, &instruction, 0),
+            immediate(&line, &instruction, 1),
+        ),
+        _ => {
+            return Step {
+                line,
+                exit: Exit::Known,
+                successors: follow(EdgeKind::Next).into_iter().collect(),
+                ends_block: false,
+            }
+        }
+    };
+    // Some(true) for a jump always taken, Some(false) for one never taken
+    let taken = condition.map(|value| (value != 0) == (instruction.opcode == JNZ));
+    if taken == Some(false) {
+        return Step {
+            line,
+            exit: Exit::Known,
+            successors: follow(EdgeKind::Next).into_iter().collect(),
+            ends_block: false,
+        };
+    }
+    let inside = |target: i64| target >= 0 && (target as usize) < words.len();
+    let mut successors = Vec::new();
+    let exit = match (target, taken) {
+        (Some(target), Some(true)) if inside(target) => {
+            let call = address >= 4 && stored_constant(words, address - 4) == Some(next as i64);
+            if call {
+                successors.push(Edge {
+                    target: target as usize,
+                    kind: EdgeKind::Call,
+                });
+                successors.extend(follow(EdgeKind::AfterCall));
+            } else {
+                successors.push(Edge {
+                    target: target as usize,
+                    kind: EdgeKind::Jump,
+                });
+            }
+            Exit::Known
+        }
+        (Some(target), _) if inside(target) => {
+            successors.push(Edge {
+                target: target as usize,
+                kind: EdgeKind::Taken,
+            });
+            successors.extend(follow(EdgeKind::NotTaken));
+            Exit::Known
+        }
+        (target, taken) => {
+            if taken.is_none() {
+                successors.extend(follow(EdgeKind::NotTaken));
+            }
+            if target.is_some() {
+                Exit::OutOfRange
+            } else {
+                Exit::Computed
+            }
+        }
+    };
+    Step {
+        line,
+        exit,
+        successors,
+        ends_block: true,
+    }
+}
+
+impl Graph {
+    /// The graph of the code reachable from address 0.
+    pub fn build(words: &[i64]) -> Graph {
+        Graph::with_entries(words, &[0])
+    }
+
+    pub fn with_entries(words: &[i64], entries: &[usize]) -> Graph {
+        let mut steps: BTreeMap<usize, Step> = BTreeMap::new();
+        let mut leaders: BTreeSet<usize> = entries.iter().copied().collect();
+        let mut pending: Vec<usize> = entries.to_vec();
+        while let Some(address) = pending.pop() {
+            if steps.contains_key(&address) || address >= words.len() {
+                continue;
+            }
+            let step = step(words, address);
+            for edge in step.successors.iter() {
+                if step.ends_block {
+                    leaders.insert(edge.target);
+                }
+                pending.push(edge.target);
+            }
+            steps.insert(address, step);
+        }
+        // data stands apart from the code running into it
+        leaders.extend(
+            steps
+                .iter()
+                .filter(|(_, step)| step.exit == Exit::Invalid)
+                .map(|(&address, _)| address),
+        );
+
+        let mut blocks = BTreeMap::new();
+        for &start in leaders.iter() {
+            let mut address = start;
+            let mut lines = Vec::new();
+            let (exit, successors) = loop {
+                let step = match steps.get(&address) {
+                    Some(step) => step,
+                    None => break (Exit::Known, Vec::new()),
+                };
+                lines.push(step.line.clone());
+                let next = address + step.line.len();
+                if step.ends_block || leaders.contains(&next) || !steps.contains_key(&next) {
+                    break (step.exit, step.successors.clone());
+                }
+                address = next;
+            };
+            if !lines.is_empty() {
+                let block = Block {
+                    start,
+                    lines,
+                    exit,
+                    successors,
+                };
+                blocks.insert(start, block);
+            }
+        }
+        Graph {
+            entries: entries.to_vec(),
+            blocks,
+        }
+    }
+
+    pub fn entries(&self) -> &[usize] {
+        &self.entries
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.values()
+    }
+
+    /// The block starting at `start`.
+    pub fn block(&self, start: usize) -> Option<&Block> {
+        self.blocks.get(&start)
+    }
+
+    /// The block holding the instruction at `address`.
+    pub fn block_containing(&self, address: usize) -> Option<&Block> {
+        self.blocks
+            .range(..=address)
+            .map(|(_, block)| block)
+            .rev()
+            .find(|block| block.lines.iter().any(|line| line.address == address))
+    }
+
+    /// Addresses of the jumps whose target is read from memory.
+    pub fn computed_jumps(&self) -> Vec<usize> {
+        self.blocks()
+            .filter(|block| block.exit == Exit::Computed)
+            .map(|block| block.last().address)
+            .collect()
+    }
+
+    /// The graph in Graphviz DOT, one box per block.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph intcode {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        for block in self.blocks() {
+            let mut label: String = block
+                .lines
+                .iter()
+                .map(|line| format!("{}\\l", line))
+                .collect();
+            let style = match block.exit {
+                Exit::Known => "",
+                Exit::Halt => ", peripheries=2",
+                Exit::Computed => {
+                    label.push_str("computed jump\\l");
+                    ", color=red"
+                }
+                Exit::OutOfRange => {
+                    label.push_str("jump out of range\\l");
+                    ", color=red"
+                }
+                Exit::Invalid => ", color=gray",
+            };
+            writeln!(dot, "    b{} [label=\"{}\"{}];", block.start, label, style).unwrap();
+        }
+        for block in self.blocks() {
+            for edge in block.successors.iter() {
+                let attributes = match edge.kind {
+                    EdgeKind::Next | EdgeKind::Jump => "",
+                    EdgeKind::Taken => " [label=\"taken\"]",
+                    EdgeKind::NotTaken => " [label=\"not taken\"]",
+                    EdgeKind::Call => " [label=\"call\", style=bold]",
+                    EdgeKind::AfterCall => " [label=\"after call\", style=dashed]",
+                };
+                writeln!(
+                    dot,
+                    "    b{} -> b{}{};",
+                    block.start, edge.target, attributes
+                )
+                .unwrap();
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flow::{Edge, EdgeKind, Exit, Graph};
+    use crate::Program;
+
+    fn edge(target: usize, kind: EdgeKind) -> Edge {
+        Edge { target, kind }
+    }
+
+    #[test]
+    fn splits_at_jumps_and_targets() {
+        // counts [14] from 0 to 3, outputting each value
+        let program = vec![
+            4, 14, 1001, 14, 1, 14, 1007, 14, 3, 15, 1005, 15, 0, 99, 0, 0,
+        ];
+        let graph = Graph::build(&program);
+        let starts: Vec<usize> = graph.blocks().map(|block| block.start).collect();
+        assert_eq!(starts, vec![0, 13]);
+        let body = graph.block(0).unwrap();
+        assert_eq!(body.lines.len(), 4);
+        assert_eq!(body.end(), 13);
+        assert_eq!(
+            body.successors,
+            vec![edge(0, EdgeKind::Taken), edge(13, EdgeKind::NotTaken)]
+        );
+        assert_eq!(graph.block(13).unwrap().exit, Exit::Halt);
+        assert_eq!(graph.block_containing(6).unwrap().start, 0);
+        assert!(graph.block_containing(7).is_none());
+    }
+
+    #[test]
+    fn immediate_conditions_and_computed_jumps() {
+        // JZ #0 always jumps, JNZ #0 never does, and JNZ [9] reads its target
+        let program = vec![1106, 0, 6, 99, 99, 99, 1105, 0, 0, 1005, 9, 9, 99];
+        let graph = Graph::build(&program);
+        assert_eq!(
+            graph.block(0).unwrap().successors,
+            vec![edge(6, EdgeKind::Jump)]
+        );
+        assert_eq!(
+            graph.block(6).unwrap().successors,
+            vec![edge(9, EdgeKind::Next)]
+        );
+        let block = graph.block(9).unwrap();
+        assert_eq!(block.exit, Exit::Known);
+        assert_eq!(
+            block.successors,
+            vec![edge(9, EdgeKind::Taken), edge(12, EdgeKind::NotTaken)]
+        );
+
+        let computed = vec![2105, 1, 0, 99];
+        let graph = Graph::build(&computed);
+        assert_eq!(graph.computed_jumps(), vec![0]);
+        assert!(graph.block(0).unwrap().successors.is_empty());
+    }
+
+    #[test]
+    fn jumps_out_of_the_program_are_not_computed() {
+        let program = vec![1005, 6, 99999, 1106, 0, -1, 0];
+        let graph = Graph::build(&program);
+        assert!(graph.computed_jumps().is_empty());
+        let block = graph.block(0).unwrap();
+        assert_eq!(block.exit, Exit::OutOfRange);
+        assert_eq!(block.successors, vec![edge(3, EdgeKind::NotTaken)]);
+        let block = graph.block(3).unwrap();
+        assert_eq!(block.exit, Exit::OutOfRange);
+        assert!(block.successors.is_empty());
+        assert!(graph
+            .to_dot()
+            .contains("jump out of range\\l\", color=red];"));
+
+        let graph = Graph::build(&[1105, 1, 99999]);
+        assert!(graph.computed_jumps().is_empty());
+        assert_eq!(graph.block(0).unwrap().exit, Exit::OutOfRange);
+    }
+
+    #[test]
+    fn data_is_an_invalid_block() {
+        let graph = Graph::build(&[1101, 1, 1, 0, 42]);
+        let data = graph.block(4).unwrap();
+        assert_eq!(data.exit, Exit::Invalid);
+        assert_eq!(data.lines[0].to_string(), "0004: DATA 42");
+        assert_eq!(
+            graph.block(0).unwrap().successors,
+            vec![edge(4, EdgeKind::Next)]
+        );
+    }
+
+    #[test]
+    fn day9_recursive_function() {
+        let program: Vec<i64> = Program::from_path("../aoc9/input.txt").unwrap().into();
+        let graph = Graph::build(&program);
+
+        // part 2 calls 922 with its return address, 915, at [rb+0]
+        let caller = graph.block_containing(912).unwrap();
+        assert_eq!(
+            caller.successors,
+            vec![edge(922, EdgeKind::Call), edge(915, EdgeKind::AfterCall)]
+        );
+        // 922 calls itself twice, the second time with JZ #0
+        let starts: Vec<usize> = graph
+            .blocks()
+            .map(|block| block.start)
+            .filter(|&start| start >= 922)
+            .collect();
+        assert_eq!(starts, vec![922, 931, 942, 957, 964, 968]);
+        assert_eq!(
+            graph.block(931).unwrap().successors,
+            vec![edge(922, EdgeKind::Call), edge(942, EdgeKind::AfterCall)]
+        );
+        assert_eq!(
+            graph.block(942).unwrap().successors,
+            vec![edge(922, EdgeKind::Call), edge(957, EdgeKind::AfterCall)]
+        );
+        // and returns with a computed jump, the only one besides the
+        // self-test of relative mode at 323
+        assert_eq!(graph.computed_jumps(), vec![323, 970]);
+        assert_eq!(graph.block(968).unwrap().exit, Exit::Computed);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph intcode {\n"));
+        assert!(dot.contains("    b931 -> b922 [label=\"call\", style=bold];\n"));
+        assert!(dot.contains(
+            "    b968 [label=\"0968: ADJ #-3\\l0970: JZ #0, [rb+0]\\lcomputed jump\\l\", color=red];\n"
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+
+use crate::error::IntcodeError;
+use crate::machine::{Machine, Status};
+use crate::observer::Observer;
+
+/// How many instructions a history keeps unless told otherwise.
+const DEFAULT_BUDGET: usize = 1_000_000;
+
+// What one executed instruction changed, with the old values needed to
+// undo it.
+#[derive(Debug, Clone, Default)]
+struct Entry {
+    pc: usize,
+    relative_base: i64,
+    writes: Vec<(usize, i64)>,
+    input: Option<i64>,
+    output: Option<i64>,
+}
+
+impl Observer for Entry {
+    fn write(&mut self, address: usize, old: i64, _new: i64) {
+        self.writes.push((address, old));
+    }
+
+    fn input(&mut self, value: i64) {
+        self.input = Some(value);
+    }
+
+    fn output(&mut self, value: i64) {
+        self.output = Some(value);
+    }
+}
+
+/// An undo log for a machine, so execution can be stepped backwards.
+///
+/// Instructions are stepped through the history, which records what each
+/// one changed. Only the last `budget` instructions are kept; older ones are
+/// forgotten and cannot be rewound.
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: VecDeque<Entry>,
+    budget: usize,
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new(DEFAULT_BUDGET)
+    }
+}
+
+impl History {
+    pub fn new(budget: usize) -> History {
+        History {
+            entries: VecDeque::new(),
+            budget,
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Number of instructions that can be stepped back.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn step(&mut self, machine: &mut Machine) -> Result<Option<Status>, IntcodeError> {
+        self.step_with(machine, &mut ())
+    }
+
+    /// Steps `machine`, recording the instruction if it ran. `observer` sees
+    /// the step as usual.
+    pub fn step_with<O: Observer>(
+        &mut self,
+        machine: &mut Machine,
+        observer: &mut O,
+    ) -> Result<Option<Status>, IntcodeError> {
+        let mut entry = Entry {
+            pc: machine.pc,
+            relative_base: machine.relative_base,
+            ..Entry::default()
+        };
+        let status = machine.step_with(&mut (&mut entry, observer))?;
+        // suspending on input or halting leaves the machine as it was
+        if let None | Some(Status::Output(_)) = status {
+            self.push(entry);
+        }
+        Ok(status)
+    }
+
+    /// Writes to memory outside of execution, e.g. from a debugger, so that
+    /// stepping back also undoes the edit.
+    pub fn poke(&mut self, machine: &mut Machine, address: usize, value: i64) {
+        self.push(Entry {
+            pc: machine.pc,
+            relative_base: machine.relative_base,
+            writes: vec![(address, machine.memory[address])],
+            ..Entry::default()
+        });
+        machine.store(address, value);
+    }
+
+    fn push(&mut self, entry: Entry) {
+        self.entries.push_back(entry);
+        while self.entries.len() > self.budget {
+            self.entries.pop_front();
+        }
+    }
+
+    fn undo(&mut self, machine: &mut Machine) -> Option<Entry> {
+        let entry = self.entries.pop_back()?;
+        for &(address, old) in entry.writes.iter().rev() {
+            machine.store(address, old);
+        }
+        machine.pc = entry.pc;
+        machine.relative_base = entry.relative_base;
+        if let Some(value) = entry.input {
+            machine.input.push_front(value);
+        }
+        // output already taken from the machine stays taken
+        if entry.output.is_some() && machine.output.last() == entry.output.as_ref() {
+            machine.output.pop();
+        }
+        Some(entry)
+    }
+
+    /// Undoes the last instruction, returning false if there is none.
+    pub fn step_back(&mut self, machine: &mut Machine) -> bool {
+        self.undo(machine).is_some()
+    }
+
+    /// Rewinds to just before the most recent write to `address`. Returns
+    /// false, having rewound everything, if the history has no such write.
+    pub fn back_to_write(&mut self, machine: &mut Machine, address: usize) -> bool {
+        while let Some(entry) = self.undo(machine) {
+            if entry.writes.iter().any(|&(written, _)| written == address) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Rewinds to just before the instruction that produced `output()[index]`.
+    /// Returns false, having rewound everything, if it is out of reach.
+    pub fn back_to_output(&mut self, machine: &mut Machine, index: usize) -> bool {
+        while let Some(entry) = self.undo(machine) {
+            if entry.output.is_some() && machine.output.len() <= index {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{History, Machine, Program, Status};
+
+    fn run(history: &mut History, machine: &mut Machine) {
+        while let Ok(None) | Ok(Some(Status::Output(_))) = history.step(machine) {}
+    }
+
+    #[test]
+    fn steps_back_to_the_start() {
+        let program = vec![3, 11, 109, 4, 1201, 7, 1, 11, 204, 7, 99, 0];
+        let mut machine = Machine::with_input(program, &[41]);
+        let start = machine.clone();
+        let mut history = History::default();
+        run(&mut history, &mut machine);
+        assert_eq!(machine.output(), &[42]);
+        assert_eq!(history.len(), 4);
+
+        assert!(history.step_back(&mut machine));
+        assert_eq!(machine.pc(), 8);
+        assert_eq!(machine.output(), &[] as &[i64]);
+        while history.step_back(&mut machine) {}
+        assert_eq!(machine, start);
+
+        run(&mut history, &mut machine);
+        assert_eq!(machine.output(), &[42]);
+    }
+
+    #[test]
+    fn rewinds_to_writes_and_outputs() {
+        // outputs 0, 1, 2 while counting [14] up
+        let program = vec![
+            4, 14, 1001, 14, 1, 14, 1007, 14, 3, 15, 1005, 15, 0, 99, 0, 0,
+        ];
+        let mut machine = Machine::new(program);
+        let mut history = History::default();
+        run(&mut history, &mut machine);
+        assert_eq!(machine.output(), &[0, 1, 2]);
+
+        assert!(history.back_to_output(&mut machine, 1));
+        assert_eq!(machine.pc(), 0);
+        assert_eq!(machine.output(), &[0]);
+        assert_eq!(machine.memory()[14], 1);
+
+        assert!(history.back_to_write(&mut machine, 14));
+        assert_eq!(machine.pc(), 2);
+        assert_eq!(machine.memory()[14], 0);
+        assert!(!history.back_to_write(&mut machine, 14));
+        assert_eq!(machine.pc(), 0);
+    }
+
+    #[test]
+    fn budget_limits_how_far_back() {
+        let mut machine = Machine::new(vec![1101, 1, 1, 9, 1101, 2, 2, 9, 99, 0]);
+        let mut history = History::new(1);
+        run(&mut history, &mut machine);
+        assert_eq!(history.len(), 1);
+        assert!(history.step_back(&mut machine));
+        assert_eq!(machine.memory()[9], 2);
+        assert!(!history.step_back(&mut machine));
+        assert_eq!(machine.pc(), 4);
+    }
+
+    #[test]
+    fn pokes_are_undone() {
+        let mut machine = Machine::new(vec![99, 7]);
+        let mut history = History::default();
+        history.poke(&mut machine, 1, 8);
+        history.poke(&mut machine, 5000, 1);
+        assert_eq!(machine.memory()[5000], 1);
+        history.step_back(&mut machine);
+        history.step_back(&mut machine);
+        assert_eq!(machine, Machine::new(vec![99, 7]));
+    }
+
+    #[test]
+    fn day9_rewind_and_replay() {
+        let program = Program::from_path("../aoc9/input.txt").unwrap();
+        let mut machine = Machine::with_input(program, &[1]);
+        let mut history = History::default();
+        run(&mut history, &mut machine);
+        let finished = machine.clone();
+        assert!(history.back_to_output(&mut machine, 0));
+        assert_eq!(machine.output(), &[] as &[i64]);
+        run(&mut history, &mut machine);
+        assert_eq!(machine, finished);
+        assert_eq!(machine.output(), &[3345854957]);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{ADD, ADJ, EQU, HLT, IMMEDIATE, INP, JNZ, JZ, LES, MUL, OUT, POSITION, RELATIVE};
+
+/// An instruction word split into its opcode and parameter modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: i64,
+    pub modes: [i64; 3],
+}
+
+impl Instruction {
+    /// Splits a word the way the interpreter does, without validating it.
+    pub fn decode(word: i64) -> Instruction {
+        Instruction {
+            opcode: word % 100,
+            modes: [(word / 100) % 10, (word / 1000) % 10, (word / 10000) % 10],
+        }
+    }
+
+    /// Decodes a word only if it is a well-formed instruction: a known
+    /// opcode, valid modes for its parameters, no mode digits beyond them,
+    /// and no destination in immediate mode.
+    pub fn parse(word: i64) -> Option<Instruction> {
+        let instruction = Instruction::decode(word);
+        mnemonic(instruction.opcode)?;
+        if word < 0 || instruction.encode() != word {
+            return None;
+        }
+        let parameters = instruction.parameters();
+        let modes_valid = instruction.modes[..parameters]
+            .iter()
+            .all(|&mode| mode == POSITION || mode == IMMEDIATE || mode == RELATIVE);
+        let writes_immediate = instruction
+            .destination()
+            .is_some_and(|index| instruction.modes[index] == IMMEDIATE);
+        if !modes_valid || writes_immediate {
+            return None;
+        }
+        Some(instruction)
+    }
+
+    pub fn encode(&self) -> i64 {
+        let modes = self.modes[..self.parameters()]
+            .iter()
+            .rev()
+            .fold(0, |digits, &mode| digits * 10 + mode);
+        modes * 100 + self.opcode
+    }
+
+    pub fn mnemonic(&self) -> Option<&'static str> {
+        mnemonic(self.opcode)
+    }
+
+    pub fn parameters(&self) -> usize {
+        match self.opcode {
+            ADD | MUL | LES | EQU => 3,
+            JNZ | JZ => 2,
+            INP | OUT | ADJ => 1,
+            _ => 0,
+        }
+    }
+
+    /// Number of words the instruction occupies, including the opcode.
+    pub fn size(&self) -> usize {
+        1 + self.parameters()
+    }
+
+    /// Index of the parameter the instruction writes to, if any.
+    pub fn destination(&self) -> Option<usize> {
+        match self.opcode {
+            ADD | MUL | LES | EQU => Some(2),
+            INP => Some(0),
+            _ => None,
+        }
+    }
+}
+
+pub fn mnemonic(opcode: i64) -> Option<&'static str> {
+    match opcode {
+        ADD => Some("ADD"),
+        MUL => Some("MUL"),
+        INP => Some("INP"),
+        OUT => Some("OUT"),
+        JNZ => Some("JNZ"),
+        JZ => Some("JZ"),
+        LES => Some("LES"),
+        EQU => Some("EQU"),
+        ADJ => Some("ADJ"),
+        HLT => Some("HLT"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction;
+    use crate::{ADD, HLT, IMMEDIATE, JZ, POSITION, RELATIVE};
+
+    #[test]
+    fn decode_splits_modes() {
+        let instruction = Instruction::decode(21201);
+        assert_eq!(instruction.opcode, ADD);
+        assert_eq!(instruction.modes, [RELATIVE, IMMEDIATE, RELATIVE]);
+        assert_eq!(instruction.size(), 4);
+        assert_eq!(instruction.mnemonic(), Some("ADD"));
+        assert_eq!(instruction.encode(), 21201);
+    }
+
+    #[test]
+    fn parse_accepts_well_formed_words() {
+        assert_eq!(Instruction::parse(2106).map(|i| i.opcode), Some(JZ));
+        assert_eq!(Instruction::parse(99).map(|i| i.opcode), Some(HLT));
+        assert_eq!(
+            Instruction::parse(1002).map(|i| i.modes),
+            Some([POSITION, IMMEDIATE, POSITION])
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_words() {
+        assert_eq!(Instruction::parse(0), None);
+        assert_eq!(Instruction::parse(99999), None);
+        assert_eq!(Instruction::parse(-1), None);
+        assert_eq!(Instruction::parse(10099), None);
+        assert_eq!(Instruction::parse(11101), None);
+        assert_eq!(Instruction::parse(301), None);
+        assert_eq!(Instruction::parse(103), None);
+        assert_eq!(Instruction::parse(1204), None);
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+
+/// Where a machine's input instructions take their values from.
+pub trait InputSource {
+    /// The next value, or `None` when there will be no more input.
+    fn next_input(&mut self) -> Option<i64>;
+}
+
+/// Where a machine's output instructions send their values.
+pub trait OutputSink {
+    fn send(&mut self, value: i64);
+}
+
+impl<S: InputSource + ?Sized> InputSource for &mut S {
+    fn next_input(&mut self) -> Option<i64> {
+        (**self).next_input()
+    }
+}
+
+impl<S: OutputSink + ?Sized> OutputSink for &mut S {
+    fn send(&mut self, value: i64) {
+        (**self).send(value)
+    }
+}
+
+impl InputSource for VecDeque<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.pop_front()
+    }
+}
+
+impl OutputSink for VecDeque<i64> {
+    fn send(&mut self, value: i64) {
+        self.push_back(value);
+    }
+}
+
+impl OutputSink for Vec<i64> {
+    fn send(&mut self, value: i64) {
+        self.push(value);
+    }
+}
+
+/// Blocks until a value arrives; a closed channel ends the input.
+impl InputSource for Receiver<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.recv().ok()
+    }
+}
+
+/// Values sent after the receiver has gone are dropped.
+impl OutputSink for Sender<i64> {
+    fn send(&mut self, value: i64) {
+        let _ = Sender::send(self, value);
+    }
+}
+
+impl OutputSink for SyncSender<i64> {
+    fn send(&mut self, value: i64) {
+        let _ = SyncSender::send(self, value);
+    }
+}
+
+/// Writes each value on a line of its own.
+impl OutputSink for io::Stdout {
+    fn send(&mut self, value: i64) {
+        let _ = writeln!(self, "{}", value);
+    }
+}
+
+/// Input from an iterator, see `from_iter`.
+pub struct Iter<I>(I);
+
+impl<I: Iterator<Item = i64>> InputSource for Iter<I> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.0.next()
+    }
+}
+
+pub fn from_iter<I: IntoIterator<Item = i64>>(values: I) -> Iter<I::IntoIter> {
+    Iter(values.into_iter())
+}
+
+/// Input produced by a closure, see `from_fn`.
+pub struct FromFn<F>(F);
+
+impl<F: FnMut() -> Option<i64>> InputSource for FromFn<F> {
+    fn next_input(&mut self) -> Option<i64> {
+        (self.0)()
+    }
+}
+
+pub fn from_fn<F: FnMut() -> Option<i64>>(f: F) -> FromFn<F> {
+    FromFn(f)
+}
+
+/// Output handed to a closure, see `to_fn`.
+pub struct ToFn<F>(F);
+
+impl<F: FnMut(i64)> OutputSink for ToFn<F> {
+    fn send(&mut self, value: i64) {
+        (self.0)(value)
+    }
+}
+
+pub fn to_fn<F: FnMut(i64)>(f: F) -> ToFn<F> {
+    ToFn(f)
+}
+
+/// Numbers read from text, separated by commas or whitespace. Input ends
+/// at end of file or at the first read error; a line with a malformed
+/// number is reported on standard error and skipped.
+pub struct Reader<R> {
+    reader: R,
+    pending: VecDeque<i64>,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> InputSource for Reader<R> {
+    fn next_input(&mut self) -> Option<i64> {
+        while self.pending.is_empty() {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let values: Result<Vec<i64>, _> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|word| !word.is_empty())
+                .map(|word| word.parse().map_err(|_| word))
+                .collect();
+            match values {
+                Ok(values) => self.pending.extend(values),
+                Err(word) => eprintln!("ignoring input line with invalid number '{}'", word),
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Numbers typed on standard input.
+pub fn stdin() -> Reader<io::StdinLock<'static>> {
+    Reader::new(io::stdin().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::mpsc::channel;
+
+    use crate::io::{from_fn, from_iter, to_fn, InputSource, OutputSink, Reader};
+
+    fn drain<S: InputSource>(mut source: S) -> Vec<i64> {
+        std::iter::from_fn(|| source.next_input()).collect()
+    }
+
+    #[test]
+    fn queues_are_first_in_first_out() {
+        let mut queue = VecDeque::new();
+        queue.send(1);
+        queue.send(2);
+        assert_eq!(drain(queue), vec![1, 2]);
+    }
+
+    #[test]
+    fn iterators_and_closures() {
+        assert_eq!(drain(from_iter(vec![3, 4])), vec![3, 4]);
+        let mut n = 0;
+        let source = from_fn(|| {
+            n += 1;
+            if n <= 3 {
+                Some(n * 10)
+            } else {
+                None
+            }
+        });
+        assert_eq!(drain(source), vec![10, 20, 30]);
+
+        let mut seen = Vec::new();
+        let mut sink = to_fn(|value| seen.push(value));
+        sink.send(5);
+        sink.send(6);
+        assert_eq!(seen, vec![5, 6]);
+    }
+
+    #[test]
+    fn channels() {
+        let (mut sender, receiver) = channel();
+        OutputSink::send(&mut sender, 7);
+        OutputSink::send(&mut sender, 8);
+        drop(sender);
+        assert_eq!(drain(receiver), vec![7, 8]);
+    }
+
+    #[test]
+    fn reads_numbers_from_text() {
+        let text = "1, 2\n\n-3 4\n";
+        assert_eq!(drain(Reader::new(text.as_bytes())), vec![1, 2, -3, 4]);
+        assert_eq!(drain(Reader::new("5 x 6\n7".as_bytes())), vec![7]);
+    }
+}
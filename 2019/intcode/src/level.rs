@@ -0,0 +1,160 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ErrorKind;
+use crate::instruction::Instruction;
+use crate::{ADD, ADJ, EQU, HLT, IMMEDIATE, INP, JNZ, JZ, LES, MUL, OUT, POSITION, RELATIVE};
+
+/// The instruction set as it grew over the puzzles. A machine running at a
+/// lower level faults on opcodes and modes that level does not have, and
+/// decodes instructions the way that day's interpreter did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// `ADD`, `MUL` and `HLT`, with no modes: the word must be the bare
+    /// opcode.
+    Day2,
+    /// Adds input, output, jumps and comparisons, and immediate mode. The
+    /// mode of a parameter written to is ignored, it is always an address.
+    Day5,
+    /// Adds `ADJ` and relative mode: the full instruction set.
+    #[default]
+    Day9,
+}
+
+impl Level {
+    pub fn opcodes(self) -> &'static [i64] {
+        match self {
+            Level::Day2 => &[ADD, MUL, HLT],
+            Level::Day5 => &[ADD, MUL, INP, OUT, JNZ, JZ, LES, EQU, HLT],
+            Level::Day9 => &[ADD, MUL, INP, OUT, JNZ, JZ, LES, EQU, ADJ, HLT],
+        }
+    }
+
+    pub fn modes(self) -> &'static [i64] {
+        match self {
+            Level::Day2 => &[POSITION],
+            Level::Day5 => &[POSITION, IMMEDIATE],
+            Level::Day9 => &[POSITION, IMMEDIATE, RELATIVE],
+        }
+    }
+
+    pub fn has_opcode(self, opcode: i64) -> bool {
+        self.opcodes().contains(&opcode)
+    }
+
+    pub fn has_mode(self, mode: i64) -> bool {
+        self.modes().contains(&mode)
+    }
+
+    /// Decodes `word` as a machine at this level executes it, or gives the
+    /// fault it raises.
+    pub fn decode(self, word: i64) -> Result<Instruction, ErrorKind> {
+        let mut instruction = Instruction::decode(word);
+        if !self.has_opcode(instruction.opcode)
+            || (self == Level::Day2 && word != instruction.opcode)
+        {
+            return Err(ErrorKind::UnknownOpcode);
+        }
+        if let (Level::Day5, Some(index)) = (self, instruction.destination()) {
+            instruction.modes[index] = POSITION;
+        }
+        match instruction.modes[..instruction.parameters()]
+            .iter()
+            .find(|&&mode| !self.has_mode(mode))
+        {
+            Some(&mode) => Err(ErrorKind::UnknownMode(mode)),
+            None => Ok(instruction),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Day2 => write!(f, "day2"),
+            Level::Day5 => write!(f, "day5"),
+            Level::Day9 => write!(f, "day9"),
+        }
+    }
+}
+
+/// Accepts `day2` or just `2`, and so on.
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Level, String> {
+        match s.trim().trim_start_matches("day") {
+            "2" => Ok(Level::Day2),
+            "5" => Ok(Level::Day5),
+            "9" => Ok(Level::Day9),
+            _ => Err(format!("unknown level '{}', expected 2, 5 or 9", s.trim())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ErrorKind, Level, Machine, ADJ, INP};
+
+    fn run_at(level: Level, program: Vec<i64>) -> Result<Machine, ErrorKind> {
+        let mut machine = Machine::new(program);
+        machine.set_level(level);
+        machine.run().map(|_| machine).map_err(|error| error.kind)
+    }
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!("day5".parse(), Ok(Level::Day5));
+        assert_eq!("9".parse(), Ok(Level::Day9));
+        assert!("7".parse::<Level>().is_err());
+        assert_eq!(Level::Day2.to_string(), "day2");
+        assert!(Level::Day5.has_opcode(INP) && !Level::Day5.has_opcode(ADJ));
+    }
+
+    #[test]
+    fn rejects_what_the_level_lacks() {
+        let immediate = vec![1101, 2, 3, 0, 99];
+        assert_eq!(
+            run_at(Level::Day2, immediate.clone()),
+            Err(ErrorKind::UnknownOpcode)
+        );
+        assert!(run_at(Level::Day5, immediate).is_ok());
+
+        let output = vec![4, 0, 99];
+        assert_eq!(
+            run_at(Level::Day2, output.clone()),
+            Err(ErrorKind::UnknownOpcode)
+        );
+        assert!(run_at(Level::Day5, output).is_ok());
+
+        let relative = vec![109, 1, 204, 0, 99];
+        assert_eq!(
+            run_at(Level::Day5, relative.clone()),
+            Err(ErrorKind::UnknownOpcode)
+        );
+        let adjusted = vec![1105, 1, 3, 204, 0, 99];
+        assert_eq!(
+            run_at(Level::Day5, adjusted),
+            Err(ErrorKind::UnknownMode(2))
+        );
+        assert_eq!(run_at(Level::Day9, relative).unwrap().output(), &[1]);
+    }
+
+    #[test]
+    fn decodes_like_the_old_interpreters() {
+        assert_eq!(Level::Day2.decode(2).unwrap().opcode, 2);
+        assert_eq!(Level::Day2.decode(1002), Err(ErrorKind::UnknownOpcode));
+        assert_eq!(Level::Day5.decode(1109), Err(ErrorKind::UnknownOpcode));
+        assert_eq!(Level::Day5.decode(2101), Err(ErrorKind::UnknownMode(2)));
+
+        // day 5 ignored the mode of the destination
+        let write_mode = vec![11101, 2, 3, 0, 99];
+        let machine = run_at(Level::Day5, write_mode.clone()).unwrap();
+        assert_eq!(machine.memory()[0], 5);
+        assert_eq!(Level::Day5.decode(21107).unwrap().modes, [1, 1, 0]);
+        assert_eq!(
+            run_at(Level::Day9, write_mode),
+            Err(ErrorKind::ImmediateWrite)
+        );
+    }
+}
@@ -0,0 +1,57 @@
+pub mod ascii;
+pub mod asm;
+mod bigint;
+mod cache;
+pub mod calls;
+mod cell;
+pub mod debugger;
+pub mod decompile;
+pub mod disasm;
+mod error;
+pub mod flow;
+mod history;
+mod instruction;
+pub mod io;
+mod level;
+mod machine;
+mod memory;
+pub mod network;
+mod observer;
+mod pipeline;
+mod profile;
+mod program;
+mod selfmod;
+mod snapshot;
+
+pub use crate::bigint::BigInt;
+pub use crate::cell::Cell;
+pub use crate::error::{ErrorKind, IntcodeError};
+pub use crate::history::History;
+pub use crate::instruction::Instruction;
+pub use crate::io::{InputSource, OutputSink};
+pub use crate::level::Level;
+pub use crate::machine::{Machine, Status};
+pub use crate::memory::Memory;
+pub use crate::observer::{Observer, Trace};
+pub use crate::pipeline::{Finished, Outcome, Pipeline};
+pub use crate::profile::{Loop, Profile};
+pub use crate::program::{ParseError, Program};
+pub use crate::selfmod::{CodeWrite, SelfModify};
+pub use crate::snapshot::SnapshotError;
+
+pub const ADD: i64 = 1;
+pub const MUL: i64 = 2;
+pub const INP: i64 = 3;
+pub const OUT: i64 = 4;
+
+pub const JNZ: i64 = 5;
+pub const JZ: i64 = 6;
+pub const LES: i64 = 7;
+pub const EQU: i64 = 8;
+pub const ADJ: i64 = 9;
+
+pub const HLT: i64 = 99;
+
+pub const POSITION: i64 = 0;
+pub const IMMEDIATE: i64 = 1;
+pub const RELATIVE: i64 = 2;
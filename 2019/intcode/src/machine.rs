@@ -0,0 +1,709 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::cache::{Cache, Op, Param};
+use crate::cell::Cell;
+use crate::error::{ErrorKind, IntcodeError};
+use crate::instruction::Instruction;
+use crate::io::{InputSource, OutputSink};
+use crate::level::Level;
+use crate::memory::Memory;
+use crate::observer::Observer;
+use crate::{ADD, ADJ, EQU, HLT, INP, JNZ, JZ, LES, MUL, OUT};
+
+/// Why a resumed machine stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status<C = i64> {
+    /// Suspended on an input instruction; push input and resume.
+    NeedsInput,
+    Output(C),
+    Halted,
+}
+
+/// An Intcode computer: memory, program counter and relative base, plus the
+/// queued input and the output produced so far. The cell type `C` decides
+/// how arithmetic overflows, see `Cell`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Machine<C: Cell = i64> {
+    pub(crate) memory: Memory<C>,
+    pub(crate) pc: usize,
+    pub(crate) relative_base: i64,
+    pub(crate) input: VecDeque<C>,
+    pub(crate) output: Vec<C>,
+    pub(crate) level: Level,
+    cache: Cache,
+}
+
+impl Machine {
+    pub fn new<P: Into<Vec<i64>>>(program: P) -> Machine {
+        Machine::from_program(program)
+    }
+
+    pub fn with_input<P: Into<Vec<i64>>>(program: P, input: &[i64]) -> Machine {
+        let mut machine = Machine::new(program);
+        input.iter().for_each(|&value| machine.push_input(value));
+        machine
+    }
+
+    /// Runs until the program halts, taking input from `input` whenever the
+    /// queue is empty and sending output to `output` rather than keeping it.
+    pub fn run_io<I: InputSource, S: OutputSink>(
+        &mut self,
+        input: I,
+        output: S,
+    ) -> Result<(), IntcodeError> {
+        self.run_io_with(input, output, &mut ())
+    }
+
+    pub fn run_io_with<I: InputSource, S: OutputSink, O: Observer>(
+        &mut self,
+        mut input: I,
+        mut output: S,
+        observer: &mut O,
+    ) -> Result<(), IntcodeError> {
+        loop {
+            match self.resume_with(observer)? {
+                Status::Halted => return Ok(()),
+                Status::NeedsInput => match input.next_input() {
+                    Some(value) => self.push_input(value),
+                    None => return Err(self.fault(ErrorKind::InputExhausted)),
+                },
+                Status::Output(value) => {
+                    self.output.pop();
+                    output.send(value);
+                }
+            }
+        }
+    }
+}
+
+impl<C: Cell> Machine<C> {
+    /// A machine with cells of type `C`, e.g.
+    /// `Machine::<BigInt>::from_program(program)`.
+    pub fn from_program<P: Into<Vec<i64>>>(program: P) -> Machine<C> {
+        let cells: Vec<C> = program.into().into_iter().map(C::from_i64).collect();
+        Machine {
+            memory: Memory::from(cells),
+            pc: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+            output: Vec::new(),
+            level: Level::default(),
+            cache: Cache::default(),
+        }
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Restricts the machine to the instruction set of `level`.
+    pub fn set_level(&mut self, level: Level) {
+        self.level = level;
+        self.cache.clear();
+    }
+
+    pub fn memory(&self) -> &Memory<C> {
+        &self.memory
+    }
+
+    /// Memory to change freely; instructions are decoded afresh after.
+    pub fn memory_mut(&mut self) -> &mut Memory<C> {
+        self.cache.clear();
+        &mut self.memory
+    }
+
+    pub fn into_memory(self) -> Memory<C> {
+        self.memory
+    }
+
+    pub fn output(&self) -> &[C] {
+        &self.output
+    }
+
+    pub fn take_output(&mut self) -> Vec<C> {
+        std::mem::take(&mut self.output)
+    }
+
+    pub fn push_input(&mut self, value: C) {
+        self.input.push_back(value);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.memory[self.pc]
+            .to_i64()
+            .is_some_and(|word| Instruction::decode(word).opcode == HLT)
+    }
+
+    /// A fault of `kind` at the current pc, e.g. for input that ran out
+    /// while the machine was suspended.
+    pub fn fault(&self, kind: ErrorKind) -> IntcodeError<C> {
+        IntcodeError {
+            kind,
+            pc: self.pc,
+            opcode: self.memory[self.pc].clone(),
+            memory: Arc::new(self.memory.clone()),
+        }
+    }
+
+    // An address, jump target or adjustment, which must fit in an i64.
+    fn small(&self, value: &C) -> Result<i64, IntcodeError<C>> {
+        value
+            .to_i64()
+            .ok_or_else(|| self.fault(ErrorKind::AddressOverflow))
+    }
+
+    fn address(&self, param: Param) -> Result<usize, IntcodeError<C>> {
+        let address = match param {
+            Param::Position(word) => word,
+            Param::Immediate(address) => return Ok(address),
+            Param::Relative(word) => self
+                .relative_base
+                .checked_add(word)
+                .ok_or_else(|| self.fault(ErrorKind::AddressOverflow))?,
+            Param::Overflow => return Err(self.fault(ErrorKind::AddressOverflow)),
+            Param::UnknownMode(v) => return Err(self.fault(ErrorKind::UnknownMode(v))),
+        };
+        if address < 0 {
+            return Err(self.fault(ErrorKind::NegativeAddress(address)));
+        }
+        Ok(address as usize)
+    }
+
+    fn destination(&self, param: Param) -> Result<usize, IntcodeError<C>> {
+        if let Param::Immediate(_) = param {
+            return Err(self.fault(ErrorKind::ImmediateWrite));
+        }
+        self.address(param)
+    }
+
+    fn operand<O: Observer<C>>(
+        &self,
+        param: Param,
+        observer: &mut O,
+    ) -> Result<C, IntcodeError<C>> {
+        let address = self.address(param)?;
+        let value = self.memory[address].clone();
+        if !matches!(param, Param::Immediate(_)) {
+            observer.read(address, value.clone());
+        }
+        Ok(value)
+    }
+
+    fn write<O: Observer<C>>(&mut self, address: usize, value: C, observer: &mut O) {
+        observer.write(address, self.memory[address].clone(), value.clone());
+        self.store(address, value);
+    }
+
+    /// Writes to memory without telling any observer.
+    pub(crate) fn store(&mut self, address: usize, value: C) {
+        self.memory.write(address, value);
+        self.cache.invalidate(address);
+    }
+
+    fn jump(&mut self, target: &C) -> Result<(), IntcodeError<C>> {
+        let target = self.small(target)?;
+        if target < 0 {
+            return Err(self.fault(ErrorKind::NegativeJump(target)));
+        }
+        self.pc = target as usize;
+        Ok(())
+    }
+
+    fn flag(condition: bool) -> C {
+        C::from_i64(if condition { 1 } else { 0 })
+    }
+
+    /// Runs until the program halts, collecting everything it outputs.
+    pub fn run(&mut self) -> Result<(), IntcodeError<C>> {
+        self.run_with(&mut ())
+    }
+
+    pub fn run_with<O: Observer<C>>(&mut self, observer: &mut O) -> Result<(), IntcodeError<C>> {
+        loop {
+            match self.resume_with(observer)? {
+                Status::Halted => return Ok(()),
+                Status::NeedsInput => return Err(self.fault(ErrorKind::InputExhausted)),
+                Status::Output(_) => {}
+            }
+        }
+    }
+
+    /// Runs until the machine needs more input, has produced a value or halts.
+    /// All state is kept, so calling it again continues where it stopped.
+    pub fn resume(&mut self) -> Result<Status<C>, IntcodeError<C>> {
+        self.resume_with(&mut ())
+    }
+
+    pub fn resume_with<O: Observer<C>>(
+        &mut self,
+        observer: &mut O,
+    ) -> Result<Status<C>, IntcodeError<C>> {
+        loop {
+            if let Some(status) = self.step_with(observer)? {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Executes a single instruction, reporting whether it suspended the
+    /// machine. Input and halt instructions leave the pc where it is, as does
+    /// any fault.
+    pub fn step(&mut self) -> Result<Option<Status<C>>, IntcodeError<C>> {
+        self.step_with(&mut ())
+    }
+
+    /// Like `step`, reporting what the instruction does to `observer`.
+    pub fn step_with<O: Observer<C>>(
+        &mut self,
+        observer: &mut O,
+    ) -> Result<Option<Status<C>>, IntcodeError<C>> {
+        let pc = self.pc;
+        let op = match self.cache.get(pc) {
+            Some(op) => {
+                observer.fetch(pc, &op.fetched());
+                op
+            }
+            None => {
+                let word = self.memory[pc]
+                    .to_i64()
+                    .ok_or_else(|| self.fault(ErrorKind::UnknownOpcode))?;
+                let fetched = Instruction::decode(word);
+                observer.fetch(pc, &fetched);
+                // the full instruction set is checked as it executes
+                let instruction = if self.level != Level::Day9 {
+                    self.level.decode(word).map_err(|kind| self.fault(kind))?
+                } else {
+                    fetched
+                };
+                let op = Op::new(fetched, instruction, pc, &self.memory);
+                let limit = self.memory.as_slice().len();
+                self.cache.insert(pc, op, limit);
+                op
+            }
+        };
+        let [p1, p2, p3] = op.params();
+
+        match op.opcode() {
+            ADD => {
+                let op1 = self.operand(p1, observer)?;
+                let op2 = self.operand(p2, observer)?;
+                let dst = self.destination(p3)?;
+                let sum = op1
+                    .try_add(&op2)
+                    .ok_or_else(|| self.fault(ErrorKind::Overflow))?;
+                self.write(dst, sum, observer);
+                self.pc += 4;
+            }
+            MUL => {
+                let op1 = self.operand(p1, observer)?;
+                let op2 = self.operand(p2, observer)?;
+                let dst = self.destination(p3)?;
+                let product = op1
+                    .try_mul(&op2)
+                    .ok_or_else(|| self.fault(ErrorKind::Overflow))?;
+                self.write(dst, product, observer);
+                self.pc += 4;
+            }
+            INP => {
+                let dst = self.destination(p1)?;
+                match self.input.pop_front() {
+                    Some(value) => {
+                        observer.input(value.clone());
+                        self.write(dst, value, observer);
+                    }
+                    None => return Ok(Some(Status::NeedsInput)),
+                }
+                self.pc += 2;
+            }
+            OUT => {
+                let op1 = self.operand(p1, observer)?;
+                observer.output(op1.clone());
+                self.output.push(op1.clone());
+                self.pc += 2;
+                return Ok(Some(Status::Output(op1)));
+            }
+            JNZ => {
+                let op1 = self.operand(p1, observer)?;
+                let dst = self.operand(p2, observer)?;
+                if !op1.is_zero() {
+                    self.jump(&dst)?;
+                } else {
+                    self.pc += 3;
+                }
+            }
+            JZ => {
+                let op1 = self.operand(p1, observer)?;
+                let dst = self.operand(p2, observer)?;
+                if op1.is_zero() {
+                    self.jump(&dst)?;
+                } else {
+                    self.pc += 3;
+                }
+            }
+            LES => {
+                let op1 = self.operand(p1, observer)?;
+                let op2 = self.operand(p2, observer)?;
+                let dst = self.destination(p3)?;
+                self.write(dst, Self::flag(op1 < op2), observer);
+                self.pc += 4;
+            }
+            EQU => {
+                let op1 = self.operand(p1, observer)?;
+                let op2 = self.operand(p2, observer)?;
+                let dst = self.destination(p3)?;
+                self.write(dst, Self::flag(op1 == op2), observer);
+                self.pc += 4;
+            }
+            ADJ => {
+                let old = self.relative_base;
+                let op1 = self.operand(p1, observer)?;
+                self.relative_base = old
+                    .checked_add(self.small(&op1)?)
+                    .ok_or_else(|| self.fault(ErrorKind::AddressOverflow))?;
+                observer.adjust(old, self.relative_base);
+                self.pc += 2;
+            }
+            HLT => return Ok(Some(Status::Halted)),
+            _ => return Err(self.fault(ErrorKind::UnknownOpcode)),
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::num::Wrapping;
+    use std::time::Instant;
+
+    use crate::io::from_iter;
+    use crate::{BigInt, ErrorKind, IntcodeError, Machine, Program, Status};
+
+    fn run(program: Vec<i64>, input: &[i64]) -> Machine {
+        let mut machine = Machine::with_input(program, input);
+        machine.run().unwrap();
+        machine
+    }
+
+    #[test]
+    fn ex1() {
+        let machine = run(vec![1, 0, 0, 0, 99], &[]);
+        assert_eq!(machine.memory().as_slice(), &[2, 0, 0, 0, 99]);
+        assert_eq!(machine.pc(), 4);
+    }
+
+    #[test]
+    fn ex_imm_add() {
+        let machine = run(vec![1101, 1, 1, 0, 99], &[]);
+        assert_eq!(machine.memory().as_slice(), &[2, 1, 1, 0, 99]);
+    }
+
+    #[test]
+    fn ex_imm_mul() {
+        let machine = run(vec![102, 3, 1, 0, 99], &[]);
+        assert_eq!(machine.memory().as_slice(), &[9, 3, 1, 0, 99]);
+    }
+
+    #[test]
+    fn ex4() {
+        let machine = run(vec![1, 1, 1, 4, 99, 5, 6, 0, 99], &[]);
+        assert_eq!(
+            machine.into_memory(),
+            vec![30, 1, 1, 4, 2, 5, 6, 0, 99].into()
+        );
+    }
+
+    #[test]
+    fn input_output() {
+        let machine = run(vec![3, 0, 4, 0, 99], &[33]);
+        assert_eq!(machine.output(), &[33]);
+    }
+
+    #[test]
+    fn input_is_consumed_in_order() {
+        let machine = run(vec![3, 0, 3, 1, 4, 0, 4, 1, 99], &[7, 8]);
+        assert_eq!(machine.output(), &[7, 8]);
+    }
+
+    #[test]
+    fn equal_to_8() {
+        let program = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        assert_eq!(run(program.clone(), &[8]).output(), &[1]);
+        assert_eq!(run(program, &[5]).output(), &[0]);
+    }
+
+    #[test]
+    fn less_than_8_imm() {
+        let program = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+        assert_eq!(run(program.clone(), &[5]).output(), &[1]);
+        assert_eq!(run(program, &[55]).output(), &[0]);
+    }
+
+    #[test]
+    fn jump_if_zero() {
+        let program = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        assert_eq!(run(program.clone(), &[0]).output(), &[0]);
+        assert_eq!(run(program, &[3]).output(), &[1]);
+    }
+
+    #[test]
+    fn jump_if_not_zero_imm() {
+        let program = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        assert_eq!(run(program.clone(), &[0]).output(), &[0]);
+        assert_eq!(run(program, &[3]).output(), &[1]);
+    }
+
+    #[test]
+    fn quine_rel() {
+        let program = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let machine = run(program.clone(), &[]);
+        assert_eq!(machine.output(), &program[..]);
+        assert_eq!(machine.relative_base(), 16);
+    }
+
+    #[test]
+    fn far_away_memory() {
+        let far = 1_000_000_000_000;
+        let machine = run(vec![1101, 3, 4, far, 4, far, 4, far + 1, 99], &[]);
+        assert_eq!(machine.output(), &[7, 0]);
+        assert_eq!(machine.memory().as_slice().len(), 9);
+        assert_eq!(machine.memory()[far as usize], 7);
+    }
+
+    #[test]
+    fn large_num_output() {
+        let machine = run(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[]);
+        assert_eq!(machine.output(), &[34915192 * 34915192]);
+    }
+
+    #[test]
+    fn large_num_input() {
+        let machine = run(vec![104, 1125899906842624, 99], &[]);
+        assert_eq!(machine.output(), &[1125899906842624]);
+    }
+
+    #[test]
+    fn rel_base_test() {
+        let machine = run(vec![109, 2000, 109, 19, 204, -2018, 99], &[]);
+        assert_eq!(machine.output(), &[2000]);
+        assert_eq!(machine.relative_base(), 2019);
+    }
+
+    #[test]
+    fn run_io_reads_sources_and_fills_sinks() {
+        let mut machine = Machine::new(vec![3, 0, 3, 1, 1, 0, 1, 0, 4, 0, 99]);
+        let mut input: VecDeque<i64> = vec![40, 2].into();
+        let mut output = Vec::new();
+        machine.run_io(&mut input, &mut output).unwrap();
+        assert_eq!(output, vec![42]);
+        assert!(input.is_empty());
+        assert_eq!(machine.output(), &[] as &[i64]);
+
+        let mut machine = Machine::with_input(vec![3, 0, 3, 1, 99], &[1]);
+        let fault = machine.run_io(from_iter(None), Vec::new()).unwrap_err();
+        assert_eq!(fault.kind, ErrorKind::InputExhausted);
+        assert_eq!(fault.pc, 2);
+    }
+
+    #[test]
+    fn resume_suspends_on_missing_input() {
+        let mut machine = Machine::new(vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0]);
+        assert_eq!(machine.resume().unwrap(), Status::NeedsInput);
+        assert_eq!(machine.pc(), 0);
+        assert_eq!(machine.resume().unwrap(), Status::NeedsInput);
+
+        machine.push_input(41);
+        assert_eq!(machine.resume().unwrap(), Status::Output(42));
+        assert_eq!(machine.pc(), 8);
+        assert_eq!(machine.resume().unwrap(), Status::Halted);
+        assert_eq!(machine.resume().unwrap(), Status::Halted);
+        assert!(machine.is_halted());
+        assert_eq!(machine.output(), &[42]);
+    }
+
+    #[test]
+    fn resume_keeps_relative_base() {
+        let program = vec![109, 20, 203, 0, 204, 0, 109, 1, 203, 0, 204, 0, 99];
+        let mut machine = Machine::new(program);
+        assert_eq!(machine.resume().unwrap(), Status::NeedsInput);
+        machine.push_input(7);
+        assert_eq!(machine.resume().unwrap(), Status::Output(7));
+        assert_eq!(machine.resume().unwrap(), Status::NeedsInput);
+        assert_eq!(machine.relative_base(), 21);
+        machine.push_input(8);
+        assert_eq!(machine.resume().unwrap(), Status::Output(8));
+        assert_eq!(machine.take_output(), vec![7, 8]);
+        assert_eq!((machine.memory()[20], machine.memory()[21]), (7, 8));
+        assert_eq!(machine.resume().unwrap(), Status::Halted);
+        assert!(machine.output().is_empty());
+    }
+
+    #[test]
+    fn step_executes_one_instruction() {
+        let mut machine = Machine::new(vec![1101, 2, 3, 5, 104, 0, 99]);
+        assert_eq!(machine.step().unwrap(), None);
+        assert_eq!(machine.pc(), 4);
+        assert_eq!(machine.step().unwrap(), Some(Status::Output(5)));
+        assert_eq!(machine.step().unwrap(), Some(Status::Halted));
+    }
+
+    fn fault(program: Vec<i64>) -> IntcodeError {
+        Machine::new(program).run().unwrap_err()
+    }
+
+    #[test]
+    fn run_without_input() {
+        let error = fault(vec![1101, 1, 1, 5, 3, 0, 99]);
+        assert_eq!(error.kind, ErrorKind::InputExhausted);
+        assert_eq!(error.pc, 4);
+        assert_eq!(error.memory[5], 2);
+        assert_eq!(error.to_string(), "0004: input exhausted");
+    }
+
+    #[test]
+    fn unknown_opcode() {
+        let error = fault(vec![1101, 1, 1, 5, 42, 0]);
+        assert_eq!(error.kind, ErrorKind::UnknownOpcode);
+        assert_eq!((error.pc, error.opcode), (4, 42));
+        assert_eq!(error.to_string(), "0004: unknown opcode 42");
+    }
+
+    #[test]
+    fn unknown_mode() {
+        let error = fault(vec![301, 0, 0, 0, 99]);
+        assert_eq!(error.kind, ErrorKind::UnknownMode(3));
+        assert_eq!(error.opcode, 301);
+    }
+
+    #[test]
+    fn negative_address() {
+        assert_eq!(fault(vec![4, -1, 99]).kind, ErrorKind::NegativeAddress(-1));
+        let error = fault(vec![109, 5, 204, -6, 99]);
+        assert_eq!((error.kind, error.pc), (ErrorKind::NegativeAddress(-1), 2));
+    }
+
+    #[test]
+    fn address_overflow() {
+        let error = fault(vec![109, i64::MAX, 204, 1, 99]);
+        assert_eq!(error.kind, ErrorKind::AddressOverflow);
+    }
+
+    #[test]
+    fn immediate_write() {
+        let error = fault(vec![11101, 1, 1, 0, 99]);
+        assert_eq!(error.kind, ErrorKind::ImmediateWrite);
+        assert_eq!(error.memory.as_slice(), &[11101, 1, 1, 0, 99]);
+    }
+
+    #[test]
+    fn negative_jump() {
+        assert_eq!(
+            fault(vec![1105, 1, -4, 99]).kind,
+            ErrorKind::NegativeJump(-4)
+        );
+    }
+
+    #[test]
+    fn machine_stays_at_the_fault() {
+        let mut machine = Machine::new(vec![104, 1, 42, 99]);
+        assert_eq!(machine.resume().unwrap(), Status::Output(1));
+        assert!(machine.resume().is_err());
+        assert_eq!(machine.pc(), 2);
+        machine.memory_mut().write(2, 99);
+        assert_eq!(machine.resume().unwrap(), Status::Halted);
+    }
+    // squares [15] three times, then outputs it
+    fn squares(start: i64) -> Vec<i64> {
+        vec![
+            2, 15, 15, 15, 2, 15, 15, 15, 2, 15, 15, 15, 4, 15, 99, start,
+        ]
+    }
+
+    #[test]
+    fn rewritten_code_is_decoded_again() {
+        // the first instruction adds, then increments its own opcode to
+        // multiply and runs again
+        let program = vec![
+            1101, 2, 3, 20, 4, 20, 1001, 0, 1, 0, 1007, 0, 1103, 21, 1005, 21, 0, 99,
+        ];
+        let machine = run(program, &[]);
+        assert_eq!(machine.output(), &[5, 6]);
+
+        let mut machine = Machine::new(vec![104, 1, 1106, 0, 0]);
+        assert_eq!(machine.resume().unwrap(), Status::Output(1));
+        machine.memory_mut().write(1, 7);
+        assert_eq!(machine.resume().unwrap(), Status::Output(7));
+    }
+
+    // Timing, so only meaningful in a release build:
+    // `cargo test --release -- --ignored cache_is_faster`
+    #[test]
+    #[ignore]
+    fn cache_is_faster() {
+        let program: Vec<i64> = Program::from_path("../aoc9/input.txt").unwrap().into();
+        let fastest = |disabled: bool| {
+            (0..200)
+                .map(|_| {
+                    let mut machine = Machine::with_input(program.clone(), &[2]);
+                    machine.cache.disabled = disabled;
+                    let start = Instant::now();
+                    machine.run().unwrap();
+                    assert_eq!(machine.output(), &[68938]);
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        };
+        let (uncached, cached) = (fastest(true), fastest(false));
+        println!("uncached {:?}, cached {:?}", uncached, cached);
+        assert!(cached < uncached);
+    }
+
+    #[test]
+    fn overflow_depends_on_the_cell_type() {
+        let error = fault(squares(1 << 16));
+        assert_eq!((error.kind, error.pc), (ErrorKind::Overflow, 4));
+        assert_eq!(fault(squares(-(1 << 16))).kind, ErrorKind::Overflow);
+
+        let mut wrapping = Machine::<Wrapping<i64>>::from_program(squares(1 << 16));
+        wrapping.run().unwrap();
+        assert_eq!(wrapping.output(), &[Wrapping(0)]);
+
+        let mut wide = Machine::<i128>::from_program(squares(1 << 8));
+        wide.run().unwrap();
+        assert_eq!(wide.output(), &[1 << 64]);
+        let error = Machine::<i128>::from_program(squares(1 << 16))
+            .run()
+            .unwrap_err();
+        assert_eq!((error.kind, error.pc), (ErrorKind::Overflow, 8));
+    }
+
+    #[test]
+    fn big_integers_never_overflow() {
+        let mut machine = Machine::<BigInt>::from_program(squares(1 << 16));
+        machine.run().unwrap();
+        assert_eq!(
+            machine.output()[0].to_string(),
+            "340282366920938463463374607431768211456"
+        );
+
+        // addresses must still fit in an i64
+        let mut machine = Machine::<BigInt>::from_program(vec![4, 0, 99]);
+        let address = "18446744073709551616".parse().unwrap();
+        machine.memory_mut().write(1, address);
+        let error = machine.run().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::AddressOverflow);
+        assert_eq!(error.opcode, BigInt::from(4));
+    }
+}
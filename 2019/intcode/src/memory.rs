@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::ops::Index;
+
+use crate::cell::Cell;
+
+const PAGE_SIZE: usize = 1024;
+// Addresses below this grow the flat vector; anything above is paged in.
+const DENSE_LIMIT: usize = 1 << 20;
+
+/// Intcode memory. Reads past anything written return zero, writes grow the
+/// flat region near the program and page in far-away addresses on demand.
+#[derive(Debug, Clone, Default)]
+pub struct Memory<C = i64> {
+    dense: Vec<C>,
+    pages: BTreeMap<usize, Box<[C]>>,
+    // what unwritten addresses read as
+    zero: C,
+}
+
+impl Memory {
+    pub fn new(program: Vec<i64>) -> Memory {
+        Memory::from(program)
+    }
+}
+
+impl<C: Cell> Memory<C> {
+    pub fn read(&self, address: usize) -> C {
+        self[address].clone()
+    }
+
+    pub fn write(&mut self, address: usize, value: C) {
+        if address < self.dense.len() {
+            self.dense[address] = value;
+        } else if address < DENSE_LIMIT {
+            self.dense.resize(address + 1, C::default());
+            self.dense[address] = value;
+        } else if let Some(page) = self.pages.get_mut(&(address / PAGE_SIZE)) {
+            page[address % PAGE_SIZE] = value;
+        } else if !value.is_zero() {
+            let mut page = vec![C::default(); PAGE_SIZE].into_boxed_slice();
+            page[address % PAGE_SIZE] = value;
+            self.pages.insert(address / PAGE_SIZE, page);
+        }
+    }
+
+    /// The flat region starting at address 0, i.e. the program and anything
+    /// written just past it.
+    pub fn as_slice(&self) -> &[C] {
+        &self.dense
+    }
+
+    /// One past the highest address that may hold a non-zero value.
+    pub fn len(&self) -> usize {
+        match self.pages.keys().next_back() {
+            Some(page) => (page + 1) * PAGE_SIZE,
+            None => self.dense.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All non-zero cells in address order.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, C)> + '_ {
+        let dense = self.dense.iter().enumerate();
+        let paged = self.pages.iter().flat_map(|(page, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .map(move |(offset, value)| (page * PAGE_SIZE + offset, value))
+        });
+        dense
+            .chain(paged)
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(address, value)| (address, value.clone()))
+    }
+}
+
+impl<C: Cell> From<Vec<C>> for Memory<C> {
+    fn from(program: Vec<C>) -> Memory<C> {
+        Memory {
+            dense: program,
+            pages: BTreeMap::new(),
+            zero: C::default(),
+        }
+    }
+}
+
+impl<C: Cell> Index<usize> for Memory<C> {
+    type Output = C;
+
+    fn index(&self, address: usize) -> &C {
+        match self.dense.get(address) {
+            Some(value) => value,
+            None => self
+                .pages
+                .get(&(address / PAGE_SIZE))
+                .map_or(&self.zero, |page| &page[address % PAGE_SIZE]),
+        }
+    }
+}
+
+// Memories are equal when every address reads the same, however they were grown.
+impl<C: Cell> PartialEq for Memory<C> {
+    fn eq(&self, other: &Memory<C>) -> bool {
+        self.cells().eq(other.cells())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::{Memory, DENSE_LIMIT};
+
+    #[test]
+    fn reads_zero_past_the_program() {
+        let memory = Memory::new(vec![1, 2, 3]);
+        assert_eq!(memory.read(2), 3);
+        assert_eq!(memory.read(3), 0);
+        assert_eq!(memory[1_000_000_000], 0);
+        assert_eq!(memory.len(), 3);
+    }
+
+    #[test]
+    fn grows_on_write() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        memory.write(10, 42);
+        assert_eq!(memory.read(10), 42);
+        assert_eq!(memory.as_slice(), &[1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 42]);
+    }
+
+    #[test]
+    fn pages_in_sparse_addresses() {
+        let mut memory = Memory::new(vec![1]);
+        memory.write(1 << 40, 7);
+        memory.write((1 << 40) + 1, 8);
+        memory.write(1 << 50, 0);
+        assert_eq!(memory.read(1 << 40), 7);
+        assert_eq!(memory[(1 << 40) + 1], 8);
+        assert_eq!(memory.read(1 << 50), 0);
+        assert_eq!(memory.as_slice(), &[1]);
+        assert_eq!(memory.pages.len(), 1);
+        assert_eq!(memory.len(), (1 << 40) + 1024);
+    }
+
+    #[test]
+    fn cells_in_address_order() {
+        let mut memory = Memory::new(vec![5, 0, 6]);
+        memory.write(DENSE_LIMIT + 3, 9);
+        memory.write(DENSE_LIMIT - 1, 4);
+        let cells: Vec<_> = memory.cells().collect();
+        assert_eq!(
+            cells,
+            vec![(0, 5), (2, 6), (DENSE_LIMIT - 1, 4), (DENSE_LIMIT + 3, 9)]
+        );
+    }
+
+    #[test]
+    fn equality_ignores_trailing_zeros() {
+        let mut grown = Memory::new(vec![1, 2]);
+        grown.write(100, 0);
+        grown.write(DENSE_LIMIT * 4, 0);
+        assert_eq!(grown, Memory::new(vec![1, 2]));
+        assert_ne!(grown, Memory::new(vec![1, 3]));
+    }
+}
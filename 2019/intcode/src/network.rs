@@ -0,0 +1,458 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use crate::error::IntcodeError;
+use crate::machine::{Machine, Status};
+
+/// Address of the NAT, which keeps the last packet sent to it and sends
+/// it to machine 0 whenever the network goes idle.
+pub const NAT: i64 = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A machine output the triple `to, x, y`.
+    Sent {
+        from: usize,
+        to: i64,
+        packet: Packet,
+    },
+    /// The network was idle and the NAT sent its packet to machine 0.
+    Wake(Packet),
+}
+
+struct Node {
+    machine: Machine,
+    queue: VecDeque<Packet>,
+    outgoing: Vec<i64>,
+}
+
+/// Machines running the same program, each told its address as its first
+/// input. Output triples `(to, x, y)` are packets routed to the input queue
+/// of machine `to`; a machine asking for input with an empty queue gets -1.
+/// Packets to addresses with no machine, other than the NAT, are dropped.
+pub struct Network {
+    nodes: Vec<Node>,
+    nat: Option<Packet>,
+    // packets sent but not yet reported, left over when `stop` ended a run
+    pending: VecDeque<(usize, i64, Packet)>,
+}
+
+impl Network {
+    pub fn new(program: &[i64], size: usize) -> Network {
+        let nodes = (0..size)
+            .map(|address| Node {
+                machine: Machine::with_input(program.to_vec(), &[address as i64]),
+                queue: VecDeque::new(),
+                outgoing: Vec::new(),
+            })
+            .collect();
+        Network {
+            nodes,
+            nat: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn machine(&self, address: usize) -> &Machine {
+        &self.nodes[address].machine
+    }
+
+    /// The packet the NAT would send on the next wake.
+    pub fn nat(&self) -> Option<Packet> {
+        self.nat
+    }
+
+    fn route(&mut self, to: i64, packet: Packet) {
+        if to == NAT {
+            self.nat = Some(packet);
+        } else if let Some(node) = self.nodes.get_mut(to as usize).filter(|_| to >= 0) {
+            node.queue.push_back(packet);
+        }
+    }
+
+    // Reports and routes the packets sent so far, returning whether `stop`
+    // ended the run.
+    fn deliver<F: FnMut(&Event) -> bool>(&mut self, events: &mut Vec<Event>, stop: &mut F) -> bool {
+        while let Some((from, to, packet)) = self.pending.pop_front() {
+            let event = Event::Sent { from, to, packet };
+            events.push(event);
+            self.route(to, packet);
+            if stop(&event) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Runs the network deterministically on the calling thread. Machines
+    /// take turns, each running until it asks for input and gets one packet
+    /// or -1. A full round in which no packet is sent or received counts as
+    /// idle.
+    ///
+    /// Returns every event up to and including the first one for which
+    /// `stop` is true, or until nothing more can happen: all machines have
+    /// halted, or the network is idle with no packet at the NAT. The packet
+    /// of the last event is delivered; any sent along with it are reported
+    /// by the next run.
+    pub fn run<F: FnMut(&Event) -> bool>(
+        &mut self,
+        mut stop: F,
+    ) -> Result<Vec<Event>, IntcodeError> {
+        let mut events = Vec::new();
+        if self.deliver(&mut events, &mut stop) {
+            return Ok(events);
+        }
+        loop {
+            let mut active = false;
+            let mut running = false;
+            for from in 0..self.nodes.len() {
+                let mut sent = Vec::new();
+                let node = &mut self.nodes[from];
+                loop {
+                    match node.machine.resume()? {
+                        Status::Output(value) => {
+                            node.machine.output.pop();
+                            node.outgoing.push(value);
+                            if let [to, x, y] = node.outgoing[..] {
+                                node.outgoing.clear();
+                                sent.push((to, Packet { x, y }));
+                            }
+                        }
+                        Status::NeedsInput => {
+                            match node.queue.pop_front() {
+                                Some(packet) => {
+                                    node.machine.push_input(packet.x);
+                                    node.machine.push_input(packet.y);
+                                    active = true;
+                                }
+                                None => node.machine.push_input(-1),
+                            }
+                            running = true;
+                            break;
+                        }
+                        Status::Halted => break,
+                    }
+                }
+                active |= !sent.is_empty();
+                self.pending
+                    .extend(sent.into_iter().map(|(to, packet)| (from, to, packet)));
+                if self.deliver(&mut events, &mut stop) {
+                    return Ok(events);
+                }
+            }
+            if !running {
+                return Ok(events);
+            }
+            if !active {
+                let packet = match self.nat {
+                    Some(packet) => packet,
+                    None => return Ok(events),
+                };
+                let event = Event::Wake(packet);
+                events.push(event);
+                self.route(0, packet);
+                if stop(&event) {
+                    return Ok(events);
+                }
+            }
+        }
+    }
+
+    /// Like `run`, but with every machine on a thread of its own and the
+    /// routing done on the calling thread. The order of events depends on
+    /// scheduling. The network is idle once every machine has been given
+    /// -1 since it last received a packet. A fault in any machine stops
+    /// them all and is returned.
+    pub fn run_threaded<F: FnMut(&Event) -> bool>(
+        self,
+        mut stop: F,
+    ) -> Result<Vec<Event>, IntcodeError> {
+        enum Message {
+            Packet(usize, i64, Packet),
+            // a machine got -1, having received this many packets in total
+            Idle(usize, usize),
+            // a machine faulted, and its thread returns the error
+            Fault,
+        }
+
+        let halt = Arc::new(AtomicBool::new(false));
+        let (router, messages) = channel();
+        for (from, to, packet) in self.pending {
+            router.send(Message::Packet(from, to, packet)).unwrap();
+        }
+        let mut inboxes: Vec<Sender<Packet>> = Vec::new();
+        let mut handles = Vec::new();
+        let mut delivered = Vec::new();
+        for (address, node) in self.nodes.into_iter().enumerate() {
+            let (inbox, packets) = channel();
+            // packets still queued from an earlier `run`
+            for &packet in node.queue.iter() {
+                inbox.send(packet).unwrap();
+            }
+            delivered.push(node.queue.len());
+            inboxes.push(inbox);
+            let router = router.clone();
+            let fault = router.clone();
+            let halt = Arc::clone(&halt);
+            let mut machine = node.machine;
+            let mut outgoing = node.outgoing;
+            let mut run = move || -> Result<(), IntcodeError> {
+                let mut received = 0;
+                while !halt.load(Ordering::Relaxed) {
+                    match machine.resume()? {
+                        Status::Output(value) => {
+                            machine.output.pop();
+                            outgoing.push(value);
+                            if let [to, x, y] = outgoing[..] {
+                                outgoing.clear();
+                                let packet = Message::Packet(address, to, Packet { x, y });
+                                if router.send(packet).is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                        Status::NeedsInput => match packets.try_recv() {
+                            Ok(packet) => {
+                                machine.push_input(packet.x);
+                                machine.push_input(packet.y);
+                                received += 1;
+                            }
+                            Err(TryRecvError::Empty) => {
+                                machine.push_input(-1);
+                                if router.send(Message::Idle(address, received)).is_err() {
+                                    break;
+                                }
+                                thread::yield_now();
+                            }
+                            Err(TryRecvError::Disconnected) => break,
+                        },
+                        Status::Halted => break,
+                    }
+                }
+                Ok(())
+            };
+            handles.push(thread::spawn(move || {
+                let result = run();
+                if result.is_err() {
+                    // the router may have stopped already
+                    let _ = fault.send(Message::Fault);
+                }
+                result
+            }));
+        }
+        // the router stops when every machine has stopped
+        drop(router);
+
+        let mut events = Vec::new();
+        let mut idle = vec![false; inboxes.len()];
+        let mut nat = self.nat;
+        let deliver = |to: usize, packet: Packet, delivered: &mut Vec<usize>| {
+            if inboxes[to].send(packet).is_ok() {
+                delivered[to] += 1;
+            }
+        };
+        for message in messages.iter() {
+            match message {
+                Message::Packet(from, to, packet) => {
+                    let event = Event::Sent { from, to, packet };
+                    events.push(event);
+                    if to == NAT {
+                        nat = Some(packet);
+                    } else if to >= 0 && (to as usize) < idle.len() {
+                        idle[to as usize] = false;
+                        deliver(to as usize, packet, &mut delivered);
+                    }
+                    if stop(&event) {
+                        break;
+                    }
+                }
+                Message::Idle(address, received) => {
+                    idle[address] = received == delivered[address];
+                    if idle.iter().all(|&idle| idle) {
+                        let packet = match nat {
+                            Some(packet) => packet,
+                            None => break,
+                        };
+                        let event = Event::Wake(packet);
+                        events.push(event);
+                        idle[0] = false;
+                        deliver(0, packet, &mut delivered);
+                        if stop(&event) {
+                            break;
+                        }
+                    }
+                }
+                Message::Fault => break,
+            }
+        }
+        halt.store(true, Ordering::Relaxed);
+        drop(messages);
+        for handle in handles {
+            handle.join().expect("network thread panicked")?;
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asm::assemble;
+    use crate::error::ErrorKind;
+    use crate::network::{Event, Network, Packet, NAT};
+
+    // Machine 0 sends (1, 7, 0). Every machine passes a packet on to the
+    // next address with y increased by one, and the last one sends it to
+    // the NAT.
+    fn relay(size: i64) -> Vec<i64> {
+        let source = format!(
+            "
+                INP -> [address]
+                JNZ [address], #receive
+                OUT #1
+                OUT #7
+                OUT #0
+            receive:
+                INP -> [x]
+                EQU [x], #-1 -> [t]
+                JNZ [t], #receive
+                INP -> [y]
+                ADD [address], #1 -> [to]
+                EQU [to], #{} -> [t]
+                JZ [t], #send
+                ADD #{}, #0 -> [to]
+            send:
+                OUT [to]
+                OUT [x]
+                ADD [y], #1 -> [y]
+                OUT [y]
+                JNZ #1, #receive
+                HLT
+            address: db 0
+            to: db 0
+            x: db 0
+            y: db 0
+            t: db 0
+            ",
+            size, NAT
+        );
+        assemble(&source).unwrap().into()
+    }
+
+    fn sent(from: usize, to: i64, y: i64) -> Event {
+        Event::Sent {
+            from,
+            to,
+            packet: Packet { x: 7, y },
+        }
+    }
+
+    #[test]
+    fn relays_packets_and_wakes_from_nat() {
+        let mut network = Network::new(&relay(3), 3);
+        let mut wakes = 0;
+        let events = network
+            .run(|event| {
+                if let Event::Wake(_) = event {
+                    wakes += 1;
+                }
+                wakes == 2
+            })
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                sent(0, 1, 0),
+                sent(1, 2, 1),
+                sent(2, NAT, 2),
+                Event::Wake(Packet { x: 7, y: 2 }),
+                sent(0, 1, 3),
+                sent(1, 2, 4),
+                sent(2, NAT, 5),
+                Event::Wake(Packet { x: 7, y: 5 }),
+            ]
+        );
+        assert_eq!(network.nat(), Some(Packet { x: 7, y: 5 }));
+    }
+
+    #[test]
+    fn stops_when_nothing_can_happen() {
+        // machine 1 is missing, so the NAT never gets a packet
+        let mut network = Network::new(&relay(3), 1);
+        assert_eq!(network.run(|_| false).unwrap(), vec![sent(0, 1, 0)]);
+        assert_eq!(network.nat(), None);
+    }
+
+    #[test]
+    fn stopping_loses_no_packets() {
+        let mut network = Network::new(&relay(3), 3);
+        let mut events = Vec::new();
+        while events.len() < 8 {
+            events.extend(network.run(|_| true).unwrap());
+        }
+        let mut count = 0;
+        let expected = Network::new(&relay(3), 3)
+            .run(|_| {
+                count += 1;
+                count == 8
+            })
+            .unwrap();
+        assert_eq!(events, expected);
+
+        // both packets are sent before the machine next asks for input
+        let source = format!(
+            "
+                INP -> [address]
+                OUT #{0}
+                OUT #7
+                OUT #1
+                OUT #{0}
+                OUT #7
+                OUT #2
+                HLT
+            address: db 0
+            ",
+            NAT
+        );
+        let program: Vec<i64> = assemble(&source).unwrap().into();
+        let mut network = Network::new(&program, 1);
+        assert_eq!(network.run(|_| true).unwrap(), vec![sent(0, NAT, 1)]);
+        assert_eq!(network.nat(), Some(Packet { x: 7, y: 1 }));
+        assert_eq!(network.run(|_| false).unwrap(), vec![sent(0, NAT, 2)]);
+        assert_eq!(network.nat(), Some(Packet { x: 7, y: 2 }));
+    }
+
+    #[test]
+    fn faults_stop_the_network() {
+        // machine 0 waits for packets forever, machine 1 jumps to opcode 98
+        let program = [3, 100, 1005, 100, 10, 3, 101, 1105, 1, 5, 98];
+        for error in [
+            Network::new(&program, 2).run(|_| false).unwrap_err(),
+            Network::new(&program, 2)
+                .run_threaded(|_| false)
+                .unwrap_err(),
+        ] {
+            assert_eq!(error.kind, ErrorKind::UnknownOpcode);
+            assert_eq!(error.pc, 10);
+        }
+    }
+
+    #[test]
+    fn threaded_matches_sequential() {
+        let program = relay(50);
+        let stop = |event: &Event| event == &Event::Wake(Packet { x: 7, y: 149 });
+        let mut network = Network::new(&program, 50);
+        let expected = network.run(stop).unwrap();
+        let events = Network::new(&program, 50).run_threaded(stop).unwrap();
+        assert_eq!(events, expected);
+        assert_eq!(events.len(), 153);
+    }
+}
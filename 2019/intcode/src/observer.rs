@@ -0,0 +1,207 @@
+use std::fmt::Display;
+use std::io::Write;
+
+use crate::instruction::Instruction;
+
+/// Hooks called by a machine as it executes. Every method defaults to doing
+/// nothing, so an observer only implements the events it cares about.
+///
+/// `read` is only called for operands in position and relative mode; the
+/// words of the instruction itself are covered by `fetch`. `C` is the cell
+/// type of the machine observed.
+#[allow(unused_variables)]
+pub trait Observer<C = i64> {
+    fn fetch(&mut self, pc: usize, instruction: &Instruction) {}
+    fn read(&mut self, address: usize, value: C) {}
+    fn write(&mut self, address: usize, old: C, new: C) {}
+    fn input(&mut self, value: C) {}
+    fn output(&mut self, value: C) {}
+    fn adjust(&mut self, old: i64, new: i64) {}
+}
+
+/// The silent observer used by `run`, `resume` and `step`.
+impl<C> Observer<C> for () {}
+
+impl<C, O: Observer<C> + ?Sized> Observer<C> for &mut O {
+    fn fetch(&mut self, pc: usize, instruction: &Instruction) {
+        (**self).fetch(pc, instruction)
+    }
+
+    fn read(&mut self, address: usize, value: C) {
+        (**self).read(address, value)
+    }
+
+    fn write(&mut self, address: usize, old: C, new: C) {
+        (**self).write(address, old, new)
+    }
+
+    fn input(&mut self, value: C) {
+        (**self).input(value)
+    }
+
+    fn output(&mut self, value: C) {
+        (**self).output(value)
+    }
+
+    fn adjust(&mut self, old: i64, new: i64) {
+        (**self).adjust(old, new)
+    }
+}
+
+/// Both observers see every event, the first one first.
+impl<C: Clone, A: Observer<C>, B: Observer<C>> Observer<C> for (A, B) {
+    fn fetch(&mut self, pc: usize, instruction: &Instruction) {
+        self.0.fetch(pc, instruction);
+        self.1.fetch(pc, instruction);
+    }
+
+    fn read(&mut self, address: usize, value: C) {
+        self.0.read(address, value.clone());
+        self.1.read(address, value);
+    }
+
+    fn write(&mut self, address: usize, old: C, new: C) {
+        self.0.write(address, old.clone(), new.clone());
+        self.1.write(address, old, new);
+    }
+
+    fn input(&mut self, value: C) {
+        self.0.input(value.clone());
+        self.1.input(value);
+    }
+
+    fn output(&mut self, value: C) {
+        self.0.output(value.clone());
+        self.1.output(value);
+    }
+
+    fn adjust(&mut self, old: i64, new: i64) {
+        self.0.adjust(old, new);
+        self.1.adjust(old, new);
+    }
+}
+
+/// Writes one line per event, e.g. `0004: OUT` followed by `  out 5`.
+/// Errors from the writer are ignored; tracing never stops a run.
+pub struct Trace<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Trace<W> {
+    pub fn new(writer: W) -> Trace<W> {
+        Trace { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: Display> Observer<C> for Trace<W> {
+    fn fetch(&mut self, pc: usize, instruction: &Instruction) {
+        let name = instruction.mnemonic().unwrap_or("???");
+        let _ = writeln!(self.writer, "{:04}: {}", pc, name);
+    }
+
+    fn read(&mut self, address: usize, value: C) {
+        let _ = writeln!(self.writer, "  read [{}] = {}", address, value);
+    }
+
+    fn write(&mut self, address: usize, old: C, new: C) {
+        let _ = writeln!(self.writer, "  write [{}] = {} (was {})", address, new, old);
+    }
+
+    fn input(&mut self, value: C) {
+        let _ = writeln!(self.writer, "  in {}", value);
+    }
+
+    fn output(&mut self, value: C) {
+        let _ = writeln!(self.writer, "  out {}", value);
+    }
+
+    fn adjust(&mut self, old: i64, new: i64) {
+        let _ = writeln!(self.writer, "  rb {} -> {}", old, new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Instruction, Machine, Observer, Trace};
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Observer for Events {
+        fn fetch(&mut self, pc: usize, instruction: &Instruction) {
+            self.0.push(format!("fetch {} {}", pc, instruction.opcode));
+        }
+
+        fn read(&mut self, address: usize, value: i64) {
+            self.0.push(format!("read {} {}", address, value));
+        }
+
+        fn write(&mut self, address: usize, old: i64, new: i64) {
+            self.0.push(format!("write {} {} {}", address, old, new));
+        }
+
+        fn input(&mut self, value: i64) {
+            self.0.push(format!("input {}", value));
+        }
+
+        fn output(&mut self, value: i64) {
+            self.0.push(format!("output {}", value));
+        }
+
+        fn adjust(&mut self, old: i64, new: i64) {
+            self.0.push(format!("adjust {} {}", old, new));
+        }
+    }
+
+    #[test]
+    fn reports_every_event() {
+        let program = vec![3, 11, 1001, 11, 2, 12, 109, 5, 204, 7, 99, 0, 0];
+        let mut machine = Machine::with_input(program, &[40]);
+        let mut events = Events::default();
+        machine.run_with(&mut events).unwrap();
+        assert_eq!(machine.output(), &[42]);
+        assert_eq!(
+            events.0,
+            vec![
+                "fetch 0 3",
+                "input 40",
+                "write 11 0 40",
+                "fetch 2 1",
+                "read 11 40",
+                "write 12 0 42",
+                "fetch 6 9",
+                "adjust 0 5",
+                "fetch 8 4",
+                "read 12 42",
+                "output 42",
+                "fetch 10 99",
+            ]
+        );
+    }
+
+    #[test]
+    fn suspended_input_is_fetched_again() {
+        let mut machine = Machine::new(vec![3, 3, 99, 0]);
+        let mut events = Events::default();
+        machine.resume_with(&mut events).unwrap();
+        machine.push_input(1);
+        machine.step_with(&mut events).unwrap();
+        assert_eq!(
+            events.0,
+            vec!["fetch 0 3", "fetch 0 3", "input 1", "write 3 0 1"]
+        );
+    }
+
+    #[test]
+    fn trace_writes_lines() {
+        let mut machine = Machine::new(vec![104, 7, 99]);
+        let mut trace = Trace::new(Vec::new());
+        machine.run_with(&mut trace).unwrap();
+        let text = String::from_utf8(trace.into_inner()).unwrap();
+        assert_eq!(text, "0000: OUT\n  out 7\n0002: HLT\n");
+    }
+}
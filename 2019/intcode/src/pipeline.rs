@@ -0,0 +1,327 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use crate::error::IntcodeError;
+use crate::io::{InputSource, OutputSink};
+use crate::machine::Machine;
+
+/// How a machine ended up: its final state, and whether it halted or
+/// faulted. A machine waiting for input faults with `InputExhausted` once
+/// every machine feeding it has stopped, or once all the machines still
+/// running are waiting for input at the same time.
+#[derive(Debug)]
+pub struct Outcome {
+    pub machine: Machine,
+    pub result: Result<(), IntcodeError>,
+}
+
+#[derive(Debug)]
+pub struct Finished {
+    /// Values output by the machines marked with `collect`, in the order
+    /// they were produced.
+    pub outputs: Vec<i64>,
+    /// One per machine, in the order they were added.
+    pub outcomes: Vec<Outcome>,
+}
+
+struct Node {
+    machine: Machine,
+    targets: Vec<usize>,
+    collect: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Running,
+    Waiting,
+    Stopped,
+}
+
+// What the running machines share: the input queued for each, what each
+// is doing, and how many machines still running feed each.
+struct Board {
+    queues: Vec<VecDeque<i64>>,
+    states: Vec<State>,
+    feeders: Vec<usize>,
+    starved: bool,
+}
+
+type Shared = Arc<(Mutex<Board>, Condvar)>;
+
+// A machine's input, taken from its queue on the board.
+struct Port {
+    shared: Shared,
+    index: usize,
+}
+
+// Sends every value to each connected machine, and to the collector.
+struct FanOut {
+    shared: Shared,
+    index: usize,
+    targets: Vec<usize>,
+    collector: Option<Sender<i64>>,
+}
+
+impl InputSource for Port {
+    fn next_input(&mut self) -> Option<i64> {
+        let (board, changed) = &*self.shared;
+        let mut board = board.lock().unwrap();
+        loop {
+            if let Some(value) = board.queues[self.index].pop_front() {
+                return Some(value);
+            }
+            board.states[self.index] = State::Waiting;
+            if !board.starved && board.states.iter().all(|&state| state != State::Running) {
+                board.starved = true;
+                changed.notify_all();
+            }
+            if board.starved || board.feeders[self.index] == 0 {
+                board.states[self.index] = State::Running;
+                return None;
+            }
+            board = changed.wait(board).unwrap();
+        }
+    }
+}
+
+impl OutputSink for FanOut {
+    fn send(&mut self, value: i64) {
+        let (board, changed) = &*self.shared;
+        let mut board = board.lock().unwrap();
+        for &target in self.targets.iter() {
+            if board.states[target] != State::Stopped {
+                board.queues[target].push_back(value);
+                board.states[target] = State::Running;
+            }
+        }
+        changed.notify_all();
+        if let Some(collector) = self.collector.as_mut() {
+            OutputSink::send(collector, value);
+        }
+    }
+}
+
+impl FanOut {
+    // Marks the machine stopped, so that what it fed no longer waits for it.
+    fn stop(&self) {
+        let (board, changed) = &*self.shared;
+        let mut board = board.lock().unwrap();
+        board.states[self.index] = State::Stopped;
+        for &target in self.targets.iter() {
+            board.feeders[target] -= 1;
+        }
+        changed.notify_all();
+    }
+}
+
+/// Machines running on threads of their own, each output connected to the
+/// input of any number of other machines. Input already queued on a machine
+/// is consumed before anything arriving from its connections.
+#[derive(Default)]
+pub struct Pipeline {
+    nodes: Vec<Node>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Machines connected one after the other, collecting the output of
+    /// the last.
+    pub fn chain<I: IntoIterator<Item = Machine>>(machines: I) -> Pipeline {
+        let mut pipeline = Pipeline::new();
+        for machine in machines {
+            let index = pipeline.add(machine);
+            if index > 0 {
+                pipeline.connect(index - 1, index);
+            }
+        }
+        if let Some(last) = pipeline.nodes.len().checked_sub(1) {
+            pipeline.collect(last);
+        }
+        pipeline
+    }
+
+    /// A chain whose last machine also feeds the first.
+    pub fn ring<I: IntoIterator<Item = Machine>>(machines: I) -> Pipeline {
+        let mut pipeline = Pipeline::chain(machines);
+        if let Some(last) = pipeline.nodes.len().checked_sub(1) {
+            pipeline.connect(last, 0);
+        }
+        pipeline
+    }
+
+    /// Adds a machine, returning its index for `connect` and `collect`.
+    pub fn add(&mut self, machine: Machine) -> usize {
+        self.nodes.push(Node {
+            machine,
+            targets: Vec::new(),
+            collect: false,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Sends the output of machine `from` to the input of machine `to`.
+    pub fn connect(&mut self, from: usize, to: usize) -> &mut Pipeline {
+        assert!(to < self.nodes.len(), "no machine {}", to);
+        self.nodes[from].targets.push(to);
+        self
+    }
+
+    /// Includes the output of machine `from` in `Finished::outputs`.
+    pub fn collect(&mut self, from: usize) -> &mut Pipeline {
+        self.nodes[from].collect = true;
+        self
+    }
+
+    /// Runs every machine on its own thread until all have stopped.
+    pub fn run(self) -> Finished {
+        let (collector, collected) = channel();
+        let mut feeders = vec![0; self.nodes.len()];
+        for &target in self.nodes.iter().flat_map(|node| node.targets.iter()) {
+            feeders[target] += 1;
+        }
+        let board = Board {
+            queues: vec![VecDeque::new(); self.nodes.len()],
+            states: vec![State::Running; self.nodes.len()],
+            feeders,
+            starved: false,
+        };
+        let shared: Shared = Arc::new((Mutex::new(board), Condvar::new()));
+        let handles: Vec<_> = self
+            .nodes
+            .into_iter()
+            .enumerate()
+            .map(|(index, node)| {
+                let input = Port {
+                    shared: shared.clone(),
+                    index,
+                };
+                let mut output = FanOut {
+                    shared: shared.clone(),
+                    index,
+                    collector: Some(collector.clone()).filter(|_| node.collect),
+                    targets: node.targets,
+                };
+                let mut machine = node.machine;
+                thread::spawn(move || {
+                    let result = machine.run_io(input, &mut output);
+                    output.stop();
+                    Outcome { machine, result }
+                })
+            })
+            .collect();
+        // only the machines hold senders now
+        drop(collector);
+
+        let outcomes = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("machine thread panicked"))
+            .collect();
+        Finished {
+            outputs: collected.iter().collect(),
+            outcomes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ErrorKind, Machine, Pipeline};
+
+    fn amplifiers(program: &[i64], phases: &[i64]) -> Vec<Machine> {
+        phases
+            .iter()
+            .enumerate()
+            .map(|(index, &phase)| {
+                let input = if index == 0 {
+                    vec![phase, 0]
+                } else {
+                    vec![phase]
+                };
+                Machine::with_input(program.to_vec(), &input)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chain_of_amplifiers() {
+        let program = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        let finished = Pipeline::chain(amplifiers(&program, &[4, 3, 2, 1, 0])).run();
+        assert_eq!(finished.outputs, vec![43210]);
+        assert!(finished.outcomes.iter().all(|o| o.result.is_ok()));
+    }
+
+    #[test]
+    fn ring_of_amplifiers() {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let finished = Pipeline::ring(amplifiers(&program, &[9, 8, 7, 6, 5])).run();
+        assert_eq!(finished.outputs.last(), Some(&139629729));
+        assert_eq!(finished.outputs.len(), 5);
+    }
+
+    #[test]
+    fn starved_ring() {
+        // each echoes one value, but nothing starts them off
+        let echo = Machine::new(vec![3, 0, 4, 0, 99]);
+        let finished = Pipeline::ring(vec![echo.clone(), echo]).run();
+        assert!(finished.outputs.is_empty());
+        for outcome in finished.outcomes.iter() {
+            let error = outcome.result.as_ref().unwrap_err();
+            assert_eq!((error.kind, error.pc), (ErrorKind::InputExhausted, 0));
+        }
+
+        // one value goes around once, then both wait for a second
+        let first = Machine::new(vec![104, 5, 3, 20, 3, 20, 99]);
+        let second = Machine::new(vec![3, 20, 4, 20, 3, 20, 99]);
+        let finished = Pipeline::ring(vec![first, second]).run();
+        assert_eq!(finished.outputs, vec![5]);
+        for outcome in finished.outcomes.iter() {
+            let error = outcome.result.as_ref().unwrap_err();
+            assert_eq!((error.kind, error.pc), (ErrorKind::InputExhausted, 4));
+        }
+    }
+
+    #[test]
+    fn fan_out_and_starved_machines() {
+        // doubles its input, adds one to its input, and adds up two inputs
+        let double = Machine::new(vec![3, 9, 1002, 9, 2, 9, 4, 9, 99, 0]);
+        let inc = Machine::new(vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0]);
+        let sum = Machine::new(vec![3, 11, 3, 12, 1, 11, 12, 11, 4, 11, 99, 0, 0]);
+        let source = Machine::new(vec![104, 20, 99]);
+        let mut pipeline = Pipeline::new();
+        let source = pipeline.add(source);
+        let double = pipeline.add(double);
+        let inc = pipeline.add(inc);
+        let sum = pipeline.add(sum);
+        let starved = pipeline.add(Machine::new(vec![3, 0, 3, 0, 99]));
+        pipeline
+            .connect(source, double)
+            .connect(source, inc)
+            .connect(double, sum)
+            .connect(inc, sum)
+            .connect(source, starved)
+            .collect(sum)
+            .collect(inc);
+        let finished = pipeline.run();
+
+        let mut outputs = finished.outputs.clone();
+        outputs.sort_unstable();
+        assert_eq!(outputs, vec![21, 61]);
+        assert!(finished.outcomes[..4].iter().all(|o| o.result.is_ok()));
+        let starved = &finished.outcomes[starved];
+        assert_eq!(
+            starved.result.as_ref().unwrap_err().kind,
+            ErrorKind::InputExhausted
+        );
+        assert_eq!(starved.machine.pc(), 2);
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::disasm::decode_memory;
+use crate::instruction::{mnemonic, Instruction};
+use crate::memory::Memory;
+use crate::observer::Observer;
+
+/// A loop found by its backward jump: the code from `start` to `end`
+/// (the address of the jumping instruction) ran again `iterations` times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Loop {
+    pub start: usize,
+    pub end: usize,
+    pub iterations: u64,
+    /// Instructions executed at addresses within the loop.
+    pub instructions: u64,
+}
+
+/// Counts what a machine does: executions per pc and per opcode, reads and
+/// writes per address, and the jumps back to a lower address that make up
+/// its loops.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    executions: HashMap<usize, u64>,
+    opcodes: BTreeMap<i64, u64>,
+    reads: HashMap<usize, u64>,
+    writes: HashMap<usize, u64>,
+    // (target, source) of every jump to a lower address
+    backward_jumps: HashMap<(usize, usize), u64>,
+    last_pc: Option<usize>,
+}
+
+impl<C> Observer<C> for Profile {
+    fn fetch(&mut self, pc: usize, instruction: &Instruction) {
+        *self.executions.entry(pc).or_default() += 1;
+        *self.opcodes.entry(instruction.opcode).or_default() += 1;
+        if let Some(last) = self.last_pc.filter(|&last| pc < last) {
+            *self.backward_jumps.entry((pc, last)).or_default() += 1;
+        }
+        self.last_pc = Some(pc);
+    }
+
+    fn read(&mut self, address: usize, _value: C) {
+        *self.reads.entry(address).or_default() += 1;
+    }
+
+    fn write(&mut self, address: usize, _old: C, _new: C) {
+        *self.writes.entry(address).or_default() += 1;
+    }
+}
+
+// The `count` largest entries, largest first and lowest key on ties.
+fn top<K: Copy + Ord>(counts: impl Iterator<Item = (K, u64)>, count: usize) -> Vec<(K, u64)> {
+    let mut counts: Vec<(K, u64)> = counts.collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts.truncate(count);
+    counts
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    /// Total number of instructions fetched.
+    pub fn total(&self) -> u64 {
+        self.opcodes.values().sum()
+    }
+
+    pub fn executions(&self, pc: usize) -> u64 {
+        self.executions.get(&pc).copied().unwrap_or(0)
+    }
+
+    pub fn opcode_count(&self, opcode: i64) -> u64 {
+        self.opcodes.get(&opcode).copied().unwrap_or(0)
+    }
+
+    pub fn reads(&self, address: usize) -> u64 {
+        self.reads.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn writes(&self, address: usize) -> u64 {
+        self.writes.get(&address).copied().unwrap_or(0)
+    }
+
+    /// The `count` most executed addresses, most executed first.
+    pub fn hottest(&self, count: usize) -> Vec<(usize, u64)> {
+        top(self.executions.iter().map(|(&pc, &n)| (pc, n)), count)
+    }
+
+    /// Loops ordered by the instructions executed inside them.
+    pub fn hot_loops(&self, count: usize) -> Vec<Loop> {
+        let mut loops: Vec<Loop> = self
+            .backward_jumps
+            .iter()
+            .map(|(&(start, end), &iterations)| Loop {
+                start,
+                end,
+                iterations,
+                instructions: (start..=end).map(|pc| self.executions(pc)).sum(),
+            })
+            .collect();
+        loops.sort_by(|a, b| {
+            b.instructions
+                .cmp(&a.instructions)
+                .then(a.start.cmp(&b.start))
+                .then(a.end.cmp(&b.end))
+        });
+        loops.truncate(count);
+        loops
+    }
+
+    /// A readable summary: opcode mix, hottest instructions, busiest
+    /// addresses and the top `count` loops, disassembled from `memory`.
+    pub fn report(&self, memory: &Memory, count: usize) -> String {
+        let total = self.total();
+        let percent = |n: u64| 100.0 * n as f64 / total.max(1) as f64;
+        let mut text = String::new();
+        writeln!(text, "{} instructions", total).unwrap();
+
+        writeln!(text, "\nopcodes:").unwrap();
+        for (opcode, n) in top(self.opcodes.iter().map(|(&op, &n)| (op, n)), usize::MAX) {
+            let name = mnemonic(opcode).map_or_else(|| opcode.to_string(), str::to_string);
+            writeln!(text, "  {:<4} {:>12} {:>5.1}%", name, n, percent(n)).unwrap();
+        }
+
+        writeln!(text, "\nhottest instructions:").unwrap();
+        for (pc, n) in self.hottest(count) {
+            let line = decode_memory(memory, pc);
+            writeln!(text, "  {:>12} {:>5.1}%  {}", n, percent(n), line).unwrap();
+        }
+
+        writeln!(text, "\nbusiest addresses:").unwrap();
+        let reads = top(self.reads.iter().map(|(&a, &n)| (a, n)), count);
+        let writes = top(self.writes.iter().map(|(&a, &n)| (a, n)), count);
+        for (address, n) in reads {
+            writeln!(text, "  read  [{}] {}", address, n).unwrap();
+        }
+        for (address, n) in writes {
+            writeln!(text, "  write [{}] {}", address, n).unwrap();
+        }
+
+        writeln!(text, "\nhot loops:").unwrap();
+        for hot in self.hot_loops(count) {
+            writeln!(
+                text,
+                "  {:04}..{:04}: {} iterations, {} instructions ({:.1}%)",
+                hot.start,
+                hot.end,
+                hot.iterations,
+                hot.instructions,
+                percent(hot.instructions)
+            )
+            .unwrap();
+            let mut address = hot.start;
+            while address <= hot.end {
+                let line = decode_memory(memory, address);
+                writeln!(text, "    {:>12}  {}", self.executions(address), line).unwrap();
+                address += line.len();
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Loop, Machine, Profile, Program, ADD, JNZ};
+
+    // counts [14] from 0 to 3, outputting each value
+    fn counter() -> Vec<i64> {
+        vec![
+            4, 14, 1001, 14, 1, 14, 1007, 14, 3, 15, 1005, 15, 0, 99, 0, 0,
+        ]
+    }
+
+    #[test]
+    fn counts_executions_and_memory() {
+        let mut machine = Machine::new(counter());
+        let mut profile = Profile::new();
+        machine.run_with(&mut profile).unwrap();
+        assert_eq!(profile.total(), 13);
+        assert_eq!(profile.executions(0), 3);
+        assert_eq!(profile.executions(13), 1);
+        assert_eq!(profile.opcode_count(ADD), 3);
+        assert_eq!(profile.opcode_count(JNZ), 3);
+        assert_eq!(profile.reads(14), 9);
+        assert_eq!(profile.writes(14), 3);
+        assert_eq!(profile.writes(15), 3);
+        assert_eq!(profile.hottest(1), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn finds_loops() {
+        let mut machine = Machine::new(counter());
+        let mut profile = Profile::new();
+        machine.run_with(&mut profile).unwrap();
+        assert_eq!(
+            profile.hot_loops(5),
+            vec![Loop {
+                start: 0,
+                end: 10,
+                iterations: 2,
+                instructions: 12,
+            }]
+        );
+        let report = profile.report(machine.memory(), 3);
+        assert!(report.starts_with("13 instructions\n"), "{}", report);
+        assert!(report.contains(
+            "  0000..0010: 2 iterations, 12 instructions (92.3%)\n\
+             \x20              3  0000: OUT [14]\n"
+        ));
+    }
+
+    #[test]
+    fn day9_part2_hot_loop() {
+        let program = Program::from_path("../aoc9/input.txt").unwrap();
+        let mut machine = Machine::with_input(program, &[2]);
+        let mut profile = Profile::new();
+        machine.run_with(&mut profile).unwrap();
+        assert_eq!(machine.output(), &[68938]);
+        // the recursive function at 922 is where nearly all the time goes
+        assert_eq!(profile.total(), 371206);
+        assert_eq!(profile.hottest(1), vec![(922, 37119)]);
+        let hottest = profile.hot_loops(1)[0];
+        assert!(hottest.start <= 922 && hottest.end >= 970, "{:?}", hottest);
+    }
+}
@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// An Intcode program as loaded from comma-separated source, e.g. an
+/// `input.txt`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    words: Vec<i64>,
+}
+
+impl Program {
+    pub fn new(words: Vec<i64>) -> Program {
+        Program { words }
+    }
+
+    pub fn words(&self) -> &[i64] {
+        &self.words
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Program, ParseError> {
+        let mut source = String::new();
+        reader.read_to_string(&mut source)?;
+        source.parse()
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Program, ParseError> {
+        Program::from_reader(File::open(path)?)
+    }
+}
+
+impl From<Program> for Vec<i64> {
+    fn from(program: Program) -> Vec<i64> {
+        program.words
+    }
+}
+
+impl From<Vec<i64>> for Program {
+    fn from(words: Vec<i64>) -> Program {
+        Program::new(words)
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    InvalidNumber {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    MissingNumber {
+        line: usize,
+        column: usize,
+    },
+    ExpectedComma {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(error) => write!(f, "{}", error),
+            ParseError::InvalidNumber { line, column, text } => {
+                write!(f, "{}:{}: invalid number '{}'", line, column, text)
+            }
+            ParseError::MissingNumber { line, column } => {
+                write!(f, "{}:{}: missing number", line, column)
+            }
+            ParseError::ExpectedComma {
+                line,
+                column,
+                found,
+            } => write!(f, "{}:{}: expected ',' but found '{}'", line, column, found),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> ParseError {
+        ParseError::Io(error)
+    }
+}
+
+// Numbers are separated by commas with any whitespace (including newlines)
+// around them. A single trailing comma is accepted.
+impl FromStr for Program {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Program, ParseError> {
+        let mut words = Vec::new();
+        let mut expect_number = true;
+        let mut chars = source.chars().peekable();
+        let (mut line, mut column) = (1, 1);
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                if c == '\n' {
+                    line += 1;
+                    column = 1;
+                } else {
+                    column += 1;
+                }
+            } else if !expect_number {
+                if c != ',' {
+                    return Err(ParseError::ExpectedComma {
+                        line,
+                        column,
+                        found: c,
+                    });
+                }
+                chars.next();
+                column += 1;
+                expect_number = true;
+            } else if c == ',' {
+                return Err(ParseError::MissingNumber { line, column });
+            } else {
+                let mut text = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == ',' || c.is_whitespace() {
+                        break;
+                    }
+                    text.push(c);
+                    chars.next();
+                }
+                match text.parse() {
+                    Ok(word) => words.push(word),
+                    Err(_) => return Err(ParseError::InvalidNumber { line, column, text }),
+                }
+                column += text.chars().count();
+                expect_number = false;
+            }
+        }
+        Ok(Program { words })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ParseError, Program};
+
+    fn parse(source: &str) -> Vec<i64> {
+        source.parse::<Program>().unwrap().into()
+    }
+
+    fn parse_error(source: &str) -> String {
+        source.parse::<Program>().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_comma_separated_numbers() {
+        assert_eq!(parse("1,0,0,0,99"), vec![1, 0, 0, 0, 99]);
+        assert_eq!(parse("1001,224,-672,224"), vec![1001, 224, -672, 224]);
+    }
+
+    #[test]
+    fn ignores_whitespace_and_trailing_newline() {
+        assert_eq!(parse(" 3, 9 ,\n8,\t9\r\n"), vec![3, 9, 8, 9]);
+        assert_eq!(
+            parse("104,1125899906842624,99,\n"),
+            vec![104, 1125899906842624, 99]
+        );
+        assert_eq!(parse("\n"), vec![]);
+    }
+
+    #[test]
+    fn reports_line_and_column() {
+        assert_eq!(parse_error("1,2,x3"), "1:5: invalid number 'x3'");
+        assert_eq!(parse_error("1,2,\n3,,4"), "2:3: missing number");
+        assert_eq!(parse_error("1,2 3"), "1:5: expected ',' but found '3'");
+        assert_eq!(
+            parse_error("1,\n  99999999999999999999"),
+            "2:3: invalid number '99999999999999999999'"
+        );
+    }
+
+    #[test]
+    fn from_reader() {
+        let program = Program::from_reader("109,1,\n204,-1\n".as_bytes()).unwrap();
+        assert_eq!(program.words(), &[109, 1, 204, -1]);
+    }
+
+    #[test]
+    fn from_path() {
+        let program = Program::from_path("../aoc9/input.txt").unwrap();
+        assert_eq!(program.words().len(), 973);
+        assert_eq!(program.words()[..3], [1102, 34463338, 34463338]);
+
+        match Program::from_path("no/such/input.txt") {
+            Err(ParseError::Io(_)) => {}
+            other => panic!("expected io error, got {:?}", other),
+        }
+    }
+}
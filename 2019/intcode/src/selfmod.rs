@@ -0,0 +1,153 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::instruction::Instruction;
+use crate::observer::Observer;
+
+/// A write into a word that had already been executed as part of an
+/// instruction, opcode or parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeWrite {
+    /// The instruction that did the writing.
+    pub pc: usize,
+    pub address: usize,
+    pub old: i64,
+    pub new: i64,
+    /// Whether an instruction covering `address` was executed after the
+    /// write.
+    pub executed_again: bool,
+}
+
+/// Records every write to a word that was executed as code before: which
+/// instruction wrote it, the old and new values, and whether the changed
+/// code ran again.
+#[derive(Debug, Clone, Default)]
+pub struct SelfModify {
+    code: HashSet<usize>,
+    writes: Vec<CodeWrite>,
+    // indices into `writes` not yet executed again, by address
+    pending: HashMap<usize, Vec<usize>>,
+    pc: usize,
+}
+
+impl Observer for SelfModify {
+    fn fetch(&mut self, pc: usize, instruction: &Instruction) {
+        self.pc = pc;
+        for address in pc..pc + instruction.size() {
+            self.code.insert(address);
+            for index in self.pending.remove(&address).unwrap_or_default() {
+                self.writes[index].executed_again = true;
+            }
+        }
+    }
+
+    fn write(&mut self, address: usize, old: i64, new: i64) {
+        if !self.code.contains(&address) {
+            return;
+        }
+        self.pending
+            .entry(address)
+            .or_default()
+            .push(self.writes.len());
+        self.writes.push(CodeWrite {
+            pc: self.pc,
+            address,
+            old,
+            new,
+            executed_again: false,
+        });
+    }
+}
+
+impl SelfModify {
+    pub fn new() -> SelfModify {
+        SelfModify::default()
+    }
+
+    /// Whether the word at `address` has been executed as code.
+    pub fn is_code(&self, address: usize) -> bool {
+        self.code.contains(&address)
+    }
+
+    /// Writes into code, in the order they happened.
+    pub fn writes(&self) -> &[CodeWrite] {
+        &self.writes
+    }
+
+    /// One line per write into code.
+    pub fn report(&self) -> String {
+        let mut text = String::new();
+        for write in self.writes.iter() {
+            writeln!(
+                text,
+                "{:04}: [{}] = {} (was {}){}",
+                write.pc,
+                write.address,
+                write.new,
+                write.old,
+                if write.executed_again {
+                    ", executed again"
+                } else {
+                    ""
+                }
+            )
+            .unwrap();
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CodeWrite, Machine, Program, SelfModify};
+
+    #[test]
+    fn finds_writes_into_code() {
+        // OUT #1, then increments its own parameter and loops while it is
+        // below 3
+        let program = vec![104, 1, 1001, 1, 1, 1, 1007, 1, 3, 14, 1005, 14, 0, 99, 0];
+        let mut machine = Machine::new(program);
+        let mut detector = SelfModify::new();
+        machine.run_with(&mut detector).unwrap();
+        assert_eq!(machine.output(), &[1, 2]);
+        assert_eq!(
+            detector.writes(),
+            &[
+                CodeWrite {
+                    pc: 2,
+                    address: 1,
+                    old: 1,
+                    new: 2,
+                    executed_again: true,
+                },
+                CodeWrite {
+                    pc: 2,
+                    address: 1,
+                    old: 2,
+                    new: 3,
+                    executed_again: false,
+                },
+            ]
+        );
+        assert!(detector.is_code(13) && !detector.is_code(14));
+        assert_eq!(
+            detector.report(),
+            "0002: [1] = 2 (was 1), executed again\n0002: [1] = 3 (was 2)\n"
+        );
+    }
+
+    #[test]
+    fn day5_diagnostic() {
+        let program = Program::from_path("../aoc5/input.txt").unwrap();
+        let mut machine = Machine::with_input(program, &[5]);
+        let mut detector = SelfModify::new();
+        machine.run_with(&mut detector).unwrap();
+        assert_eq!(machine.output(), &[9265694]);
+        // the tests overwrite the first instruction, `1101, 294, 0, 0` at
+        // 284 and the same with 314 at 304, but never run it again
+        assert_eq!(
+            detector.report(),
+            "0284: [0] = 294 (was 3)\n0304: [0] = 314 (was 294)\n"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::level::Level;
+use crate::machine::Machine;
+use crate::memory::Memory;
+
+const HEADER: &str = "intcode-snapshot";
+pub const VERSION: u32 = 1;
+
+// Runs of non-zero cells separated by at most this many zeros are written
+// on one line.
+const MAX_GAP: usize = 16;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    NotASnapshot,
+    UnsupportedVersion(String),
+    Invalid { line: usize, message: String },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{}", error),
+            SnapshotError::NotASnapshot => write!(f, "not an intcode snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version '{}'", version)
+            }
+            SnapshotError::Invalid { line, message } => write!(f, "{}: {}", line, message),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> SnapshotError {
+        SnapshotError::Io(error)
+    }
+}
+
+fn join(values: impl Iterator<Item = i64>) -> String {
+    values.map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+}
+
+// The snapshot is line-based text so it can be read and diffed:
+//
+//   intcode-snapshot 1
+//   level day5
+//   pc 4
+//   relative_base 0
+//   input 1,2
+//   output
+//   memory 0 1101,1,2,3
+//   memory 2000000 7
+//
+// Each `memory` line gives a start address and the words from there on;
+// anything not listed is zero. `level` is only written for machines
+// restricted to a smaller instruction set.
+impl Machine {
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{} {}", HEADER, VERSION)?;
+        if self.level != Level::default() {
+            writeln!(writer, "level {}", self.level)?;
+        }
+        writeln!(writer, "pc {}", self.pc)?;
+        writeln!(writer, "relative_base {}", self.relative_base)?;
+        writeln!(writer, "input {}", join(self.input.iter().copied()))?;
+        writeln!(writer, "output {}", join(self.output.iter().copied()))?;
+
+        let mut runs: Vec<(usize, Vec<i64>)> = Vec::new();
+        for (address, value) in self.memory.cells() {
+            match runs.last_mut() {
+                Some((start, words)) if address - (*start + words.len()) <= MAX_GAP => {
+                    words.resize(address - *start, 0);
+                    words.push(value);
+                }
+                _ => runs.push((address, vec![value])),
+            }
+        }
+        for (start, words) in runs {
+            writeln!(writer, "memory {} {}", start, join(words.into_iter()))?;
+        }
+        writer.flush()
+    }
+
+    pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load<R: BufRead>(reader: R) -> Result<Machine, SnapshotError> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let version = match header.split_once(' ') {
+            Some((HEADER, version)) => version.trim().to_string(),
+            _ => return Err(SnapshotError::NotASnapshot),
+        };
+        if version != VERSION.to_string() {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut machine = Machine::new(Vec::new());
+        let mut memory = Memory::default();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            let number = index + 2;
+            let invalid = |message: String| SnapshotError::Invalid {
+                line: number,
+                message,
+            };
+            let parse = |text: &str| -> Result<i64, SnapshotError> {
+                text.trim()
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid number '{}'", text.trim())))
+            };
+            let list = |text: &str| -> Result<Vec<i64>, SnapshotError> {
+                if text.trim().is_empty() {
+                    return Ok(Vec::new());
+                }
+                text.split(',').map(parse).collect()
+            };
+            let (key, rest) = line.split_once(' ').unwrap_or((&line, ""));
+            match key {
+                "" => {}
+                "level" => machine.level = rest.parse().map_err(invalid)?,
+                "pc" => {
+                    let pc = parse(rest)?;
+                    if pc < 0 {
+                        return Err(invalid(format!("negative pc {}", pc)));
+                    }
+                    machine.pc = pc as usize;
+                }
+                "relative_base" => machine.relative_base = parse(rest)?,
+                "input" => machine.input = list(rest)?.into(),
+                "output" => machine.output = list(rest)?,
+                "memory" => {
+                    let (start, words) = rest.split_once(' ').unwrap_or((rest, ""));
+                    let start = parse(start)?;
+                    if start < 0 {
+                        return Err(invalid(format!("negative address {}", start)));
+                    }
+                    for (offset, value) in list(words)?.into_iter().enumerate() {
+                        memory.write(start as usize + offset, value);
+                    }
+                }
+                _ => return Err(invalid(format!("unknown field '{}'", key))),
+            }
+        }
+        machine.memory = memory;
+        Ok(machine)
+    }
+
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Machine, SnapshotError> {
+        Machine::load(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::SnapshotError;
+    use crate::{Level, Machine, Program, Status};
+
+    fn round_trip(machine: &Machine) -> Machine {
+        let mut bytes = Vec::new();
+        machine.save(&mut bytes).unwrap();
+        Machine::load(&bytes[..]).unwrap()
+    }
+
+    fn load_error(text: &str) -> String {
+        Machine::load(text.as_bytes()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn writes_readable_text() {
+        let mut machine = Machine::with_input(vec![3, 0, 4, 0, 99, 0, 0, 5], &[7, 8]);
+        machine.resume().unwrap();
+        machine.memory_mut().write(2_000_000, -1);
+        let mut bytes = Vec::new();
+        machine.save(&mut bytes).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "intcode-snapshot 1\n\
+             pc 4\n\
+             relative_base 0\n\
+             input 8\n\
+             output 7\n\
+             memory 0 7,0,4,0,99,0,0,5\n\
+             memory 2000000 -1\n"
+        );
+        assert_eq!(round_trip(&machine), machine);
+    }
+
+    #[test]
+    fn resumes_from_snapshot() {
+        let program = Program::from_path("../aoc9/input.txt").unwrap();
+        let mut machine = Machine::with_input(program, &[2]);
+        for _ in 0..10_000 {
+            machine.step().unwrap();
+        }
+        let mut restored = round_trip(&machine);
+        assert_eq!(restored, machine);
+        assert_eq!(restored.resume().unwrap(), Status::Output(68938));
+        assert_eq!(restored.resume().unwrap(), Status::Halted);
+    }
+
+    #[test]
+    fn saves_to_path() {
+        let name = format!("intcode-snapshot-test-{}.txt", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        let mut machine = Machine::new(vec![109, 19, 204, -34, 99]);
+        machine.step().unwrap();
+        machine.save_to_path(&path).unwrap();
+        let restored = Machine::load_from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restored.relative_base(), 19);
+        assert_eq!(restored, machine);
+    }
+
+    #[test]
+    fn keeps_the_level() {
+        let mut machine = Machine::new(vec![1101, 2, 3, 0, 99]);
+        machine.set_level(Level::Day5);
+        let mut bytes = Vec::new();
+        machine.save(&mut bytes).unwrap();
+        assert!(String::from_utf8(bytes).unwrap().contains("\nlevel day5\n"));
+        assert_eq!(round_trip(&machine).level(), Level::Day5);
+    }
+
+    #[test]
+    fn rejects_bad_snapshots() {
+        assert_eq!(load_error("1,2,3\n"), "not an intcode snapshot");
+        assert_eq!(
+            load_error("intcode-snapshot 2\npc 0\n"),
+            "unsupported snapshot version '2'"
+        );
+        assert_eq!(
+            load_error("intcode-snapshot 1\npc 0\nmemory 0 1,x\n"),
+            "3: invalid number 'x'"
+        );
+        assert_eq!(
+            load_error("intcode-snapshot 1\nregisters 1\n"),
+            "2: unknown field 'registers'"
+        );
+        match Machine::load_from_path("no/such/snapshot") {
+            Err(SnapshotError::Io(_)) => {}
+            other => panic!("expected io error, got {:?}", other),
+        }
+    }
+}
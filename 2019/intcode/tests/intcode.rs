@@ -0,0 +1,98 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Runs the binary on `program`, saved under a name unique to the test,
+// and returns its exit code, standard output and standard error.
+fn intcode(name: &str, program: &str, args: &[&str], stdin: &str) -> (i32, String, String) {
+    let name = format!("intcode-bin-test-{}-{}.txt", name, std::process::id());
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, program).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .args(args)
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+// Prints the sum of two inputs.
+const SUM: &str = "3,11,3,12,1,11,12,11,4,11,99,0,0";
+
+#[test]
+fn takes_input_from_options_then_stdin() {
+    let (code, stdout, _) = intcode("options", SUM, &["-i", "3,4"], "");
+    assert_eq!((code, stdout.as_str()), (0, "7\n"));
+    let (code, stdout, _) = intcode("stdin", SUM, &[], "3\n4\n");
+    assert_eq!((code, stdout.as_str()), (0, "7\n"));
+    let (code, stdout, _) = intcode("both", SUM, &["-i", "3"], "4\n");
+    assert_eq!((code, stdout.as_str()), (0, "7\n"));
+}
+
+#[test]
+fn fails_when_input_runs_out() {
+    let (code, stdout, stderr) = intcode("no-stdin", SUM, &["-i", "3", "--no-stdin"], "4\n");
+    assert_eq!((code, stdout.as_str()), (1, ""));
+    assert!(stderr.ends_with(": 0002: input exhausted\n"), "{}", stderr);
+    let (code, _, stderr) = intcode("empty-stdin", SUM, &[], "3\n");
+    assert_eq!(code, 1);
+    assert!(stderr.ends_with(": 0002: input exhausted\n"), "{}", stderr);
+}
+
+#[test]
+fn exits_with_1_on_a_fault() {
+    let (code, stdout, stderr) = intcode("fault", "104,5,42", &[], "");
+    assert_eq!((code, stdout.as_str()), (1, "5\n"));
+    assert!(
+        stderr.ends_with(": 0002: unknown opcode 42\n"),
+        "{}",
+        stderr
+    );
+    // INP is not a day 2 instruction
+    let (code, _, stderr) = intcode("level", SUM, &["-l", "day2", "-i", "3,4"], "");
+    assert_eq!(code, 1);
+    assert!(stderr.ends_with(": 0000: unknown opcode 3\n"), "{}", stderr);
+}
+
+#[test]
+fn selects_cells_and_ascii() {
+    let square = "3,9,2,9,9,9,4,9,99,0";
+    let (code, _, stderr) = intcode("i64", square, &["-i", "4294967296"], "");
+    assert_eq!(code, 1);
+    assert!(stderr.contains("arithmetic overflow"), "{}", stderr);
+    let (code, stdout, _) = intcode("i128", square, &["-c", "i128", "-i", "4294967296"], "");
+    assert_eq!((code, stdout.as_str()), (0, "18446744073709551616\n"));
+    let (code, stdout, _) = intcode("ascii", "3,0,4,0,99", &["-a", "-i", "A"], "");
+    assert_eq!((code, stdout.as_str()), (0, "A"));
+}
+
+#[test]
+fn rejects_bad_usage_with_exit_code_2() {
+    for args in [
+        &["--frobnicate"][..],
+        &["-l", "day7"],
+        &["-a", "-c", "big"],
+        &["-i"],
+    ] {
+        let (code, stdout, stderr) = intcode("usage", SUM, args, "");
+        assert_eq!((code, stdout.as_str()), (2, ""), "{:?}", args);
+        assert!(stderr.ends_with("try 'intcode --help'\n"), "{}", stderr);
+    }
+    let (code, stdout, _) = intcode("help", SUM, &["--help"], "");
+    assert_eq!(code, 0);
+    assert!(stdout.starts_with("usage: intcode"));
+}